@@ -1,28 +1,71 @@
-use quote::Tokens;
-use syn::{Body, Ident, VariantData, MacroInput};
-use add_like::{tuple_exprs, struct_exprs};
+use proc_macro2::{Span, TokenStream};
+use syn::{Data, DeriveInput, Fields, Ident};
+use crate::attrs::{Attrs, Kind, Spec, DEFAULT_SPEC};
+use crate::diagnostics::{self, Error};
+use crate::utils::{add_field_bounds, field_member, is_phantom_data};
 
-pub fn expand(input: &MacroInput, trait_name: &str) -> Tokens {
-    let trait_ident = Ident::from(trait_name);
+/// `skip` leaves a field as it is and `with` updates a field with a function instead of the
+/// trait method
+const SPEC: Spec = Spec {
+    type_keys: DEFAULT_SPEC.type_keys,
+    variant_keys: &[],
+    field_keys: &[("skip", Kind::Flag), ("with", Kind::Path)],
+    markers: &[],
+    shared: None,
+};
+
+pub fn expand(input: &DeriveInput, trait_name: &str) -> Result<TokenStream, Error> {
+    let trait_ident = Ident::new(trait_name, Span::call_site());
     let method_name = trait_name.to_string();
-    let method_name = method_name.trim_right_matches("Assign");
+    let method_name = method_name.trim_end_matches("Assign");
     let method_name = method_name.to_lowercase();
-    let method_ident = Ident::from(method_name.to_string() + "_assign");
+    let method_ident = Ident::new(&(method_name.to_string() + "_assign"), Span::call_site());
     let input_type = &input.ident;
 
-    let exprs = match input.body {
-        Body::Struct(VariantData::Tuple(ref fields)) => tuple_exprs(fields, &method_ident),
-        Body::Struct(VariantData::Struct(ref fields)) => struct_exprs(fields, &method_ident),
-
-        _ => panic!(format!("Only structs can use derive({})", trait_name)),
+    let attrs = Attrs::parse(input, trait_name, &SPEC);
+    let fields = match input.data {
+        Data::Struct(ref data) => {
+            match data.fields {
+                Fields::Unnamed(_) | Fields::Named(_) => Ok(&data.fields),
+                Fields::Unit => Err(diagnostics::unsupported_unit_struct(trait_name, input_type)),
+            }
+        }
+        Data::Enum(ref data) => {
+            Err(diagnostics::unsupported_enum(trait_name, data.enum_token, input_type))
+        }
+        Data::Union(ref data) => Err(diagnostics::unsupported_union(trait_name, data.union_token)),
     };
+    let (attrs, fields) = diagnostics::join(attrs, fields)?;
+
+    let generics = add_field_bounds(input, &attrs, |_| quote!(::core::ops::#trait_ident))?;
+    let exprs = exprs(fields, &attrs, &method_ident);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
-    quote!(
-        impl ::std::ops::#trait_ident for #input_type {
-            fn #method_ident(&mut self, rhs: #input_type) {
+    Ok(quote!(
+        impl #impl_generics ::core::ops::#trait_ident for #input_type #ty_generics #where_clause {
+            fn #method_ident(&mut self, rhs: #input_type #ty_generics) {
                 #(#exprs;
                   )*
             }
         }
-    )
+    ))
+}
+
+fn exprs(fields: &Fields, attrs: &Attrs, method_ident: &Ident) -> Vec<TokenStream> {
+    let mut exprs = vec![];
+
+    for (i, field) in fields.iter().enumerate() {
+        let options = attrs.field(field);
+        if options.flag("skip") || is_phantom_data(&field.ty) {
+            continue;
+        }
+        let member = field_member(i, field);
+        match options.path("with") {
+            // generates `with(&mut self.x, rhs.x)`
+            Some(with) => exprs.push(quote!(#with(&mut self.#member, rhs.#member))),
+            // generates `self.x.add_assign(rhs.x)`
+            None => exprs.push(quote!(self.#member.#method_ident(rhs.#member))),
+        }
+    }
+    exprs
 }
@@ -1,126 +1,180 @@
-use quote::{Tokens, ToTokens};
-use syn::{Body, Field, Ident, Variant, VariantData, MacroInput};
-use std::iter;
-use utils::numbered_vars;
-
-pub fn expand(input: &MacroInput, trait_name: &str) -> Tokens {
-    let trait_ident = Ident::from(trait_name);
+use proc_macro2::{Span, TokenStream};
+use quote::ToTokens;
+use syn::{Data, DataStruct, DeriveInput, Field, Fields, Ident, Index, Variant};
+use syn::punctuated::Punctuated;
+use syn::token::Comma;
+use crate::attrs::{Attrs, Kind, Spec, DEFAULT_SPEC};
+use crate::diagnostics::{self, Error};
+use crate::utils::{add_field_bounds, is_phantom_data, numbered_vars};
+
+/// `skip` keeps the value of the left hand side for a field and `with` combines a field with a
+/// function instead of the trait method
+const SPEC: Spec = Spec {
+    type_keys: DEFAULT_SPEC.type_keys,
+    variant_keys: &[],
+    field_keys: &[("skip", Kind::Flag), ("with", Kind::Path)],
+    markers: &[],
+    shared: None,
+};
+
+pub fn expand(input: &DeriveInput, trait_name: &str) -> Result<TokenStream, Error> {
+    let trait_ident = Ident::new(trait_name, Span::call_site());
     let method_name = trait_name.to_lowercase();
-    let method_ident = Ident::from(method_name);
+    let method_ident = Ident::new(&method_name, Span::call_site());
     let input_type = &input.ident;
 
-    let (output_type, block) = match input.body {
-        Body::Struct(VariantData::Tuple(ref fields)) => {
-            (quote!(#input_type), tuple_content(input_type, fields, &method_ident))
+    let attrs = Attrs::parse(input, trait_name, &SPEC);
+    let shape = match input.data {
+        Data::Struct(DataStruct { fields: Fields::Unit, .. }) => {
+            Err(diagnostics::unsupported_unit_struct(trait_name, input_type))
         }
-        Body::Struct(VariantData::Struct(ref fields)) => {
-            (quote!(#input_type), struct_content(input_type, fields, &method_ident))
+        Data::Union(ref data) => Err(diagnostics::unsupported_union(trait_name, data.union_token)),
+        _ => Ok(()),
+    };
+    let (attrs, ()) = diagnostics::join(attrs, shape)?;
+    let attrs = &attrs;
+    let generics = add_field_bounds(input,
+                                    attrs,
+                                    |ty| quote!(::core::ops::#trait_ident<Output=#ty>))?;
+    let (_, ty_generics, _) = input.generics.split_for_impl();
+
+    let (output_type, block) = match input.data {
+        Data::Struct(ref data) => {
+            match data.fields {
+                Fields::Unnamed(ref fields) => {
+                    (quote!(#input_type #ty_generics),
+                     tuple_content(input_type, &fields.unnamed, attrs, &method_ident))
+                }
+                Fields::Named(ref fields) => {
+                    (quote!(#input_type #ty_generics),
+                     struct_content(input_type, &fields.named, attrs, &method_ident))
+                }
+                Fields::Unit => unreachable!(),
+            }
         }
-        Body::Enum(ref definition) => {
-            (quote!(Result<#input_type, &'static str>),
-             enum_content(input_type, definition, &method_ident))
+        Data::Enum(ref data) => {
+            (quote!(::core::result::Result<#input_type #ty_generics, &'static str>),
+             enum_content(input_type, &data.variants, attrs, &method_ident))
         }
-
-        _ => panic!(format!("Only structs and enums can use derive({})", trait_name)),
+        Data::Union(_) => unreachable!(),
     };
 
-    quote!(
-        impl ::std::ops::#trait_ident for #input_type {
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    Ok(quote!(
+        impl #impl_generics ::core::ops::#trait_ident for #input_type #ty_generics #where_clause {
             type Output = #output_type;
-            fn #method_ident(self, rhs: #input_type) -> #output_type {
+            fn #method_ident(self, rhs: #input_type #ty_generics) -> #output_type {
                 #block
             }
         }
-    )
+    ))
 }
 
-fn tuple_content<T: ToTokens>(input_type: &T, fields: &Vec<Field>, method_ident: &Ident) -> Tokens {
-    let exprs = tuple_exprs(fields, method_ident);
+fn tuple_content<T: ToTokens>(input_type: &T,
+                              fields: &Punctuated<Field, Comma>,
+                              attrs: &Attrs,
+                              method_ident: &Ident)
+                              -> TokenStream {
+    let exprs = tuple_exprs(fields, attrs, method_ident);
     quote!(#input_type(#(#exprs),*))
 }
 
-pub fn tuple_exprs(fields: &Vec<Field>, method_ident: &Ident) -> Vec<Tokens> {
+fn tuple_exprs(fields: &Punctuated<Field, Comma>,
+               attrs: &Attrs,
+               method_ident: &Ident)
+               -> Vec<TokenStream> {
     let mut exprs = vec![];
 
-    for i in 0..fields.len() {
-        let i = Ident::from(i.to_string());
+    for (i, field) in fields.iter().enumerate() {
+        let i = Index::from(i);
         // generates `self.0.add(rhs.0)`
-        let expr = quote!(self.#i.#method_ident(rhs.#i));
-        exprs.push(expr);
+        exprs.push(field_expr(field, attrs, quote!(self.#i), quote!(rhs.#i), method_ident));
     }
-    return exprs;
-
+    exprs
 }
 
 
-fn struct_content(input_type: &Ident, fields: &Vec<Field>, method_ident: &Ident) -> Tokens {
+fn struct_content(input_type: &Ident,
+                  fields: &Punctuated<Field, Comma>,
+                  attrs: &Attrs,
+                  method_ident: &Ident)
+                  -> TokenStream {
     // It's safe to unwrap because struct fields always have an identifier
-    let exprs = struct_exprs(fields, method_ident);
+    let exprs = struct_exprs(fields, attrs, method_ident);
     let field_ids = fields.iter().map(|f| f.ident.as_ref().unwrap());
 
     quote!(#input_type{#(#field_ids: #exprs),*})
 }
 
-pub fn struct_exprs(fields: &Vec<Field>, method_ident: &Ident) -> Vec<Tokens> {
+fn struct_exprs(fields: &Punctuated<Field, Comma>,
+                attrs: &Attrs,
+                method_ident: &Ident)
+                -> Vec<TokenStream> {
     let mut exprs = vec![];
 
     for field in fields {
         // It's safe to unwrap because struct fields always have an identifier
         let field_id = field.ident.as_ref().unwrap();
-        // generates `x: self.x.add(rhs.x)`
-        let expr = quote!(self.#field_id.#method_ident(rhs.#field_id));
-        exprs.push(expr)
+        // generates `self.x.add(rhs.x)`
+        let (lhs, rhs) = (quote!(self.#field_id), quote!(rhs.#field_id));
+        exprs.push(field_expr(field, attrs, lhs, rhs, method_ident))
     }
-    return exprs;
+    exprs
 }
 
 
-fn enum_content(input_type: &Ident, variants: &Vec<Variant>, method_ident: &Ident) -> Tokens {
+fn enum_content(input_type: &Ident,
+                variants: &Punctuated<Variant, Comma>,
+                attrs: &Attrs,
+                method_ident: &Ident)
+                -> TokenStream {
     let mut matches = vec![];
-    let mut method_iter = iter::repeat(method_ident);
 
     for variant in variants {
         let subtype = &variant.ident;
         let subtype = quote!(#input_type::#subtype);
 
-        match variant.data {
-            VariantData::Tuple(ref fields) => {
+        match variant.fields {
+            Fields::Unnamed(ref fields) => {
                 // The patern that is outputted should look like this:
                 // (Subtype(left_vars), TypePath(right_vars)) => Ok(TypePath(exprs))
-                let size = fields.len();
+                let size = fields.unnamed.len();
                 let l_vars = &numbered_vars(size, "l_");
                 let r_vars = &numbered_vars(size, "r_");
-                let method_iter = method_iter.by_ref();
+                let exprs = var_exprs(&fields.unnamed, attrs, l_vars, r_vars, method_ident);
                 let matcher = quote!{
                     (#subtype(#(#l_vars),*),
                      #subtype(#(#r_vars),*)) => {
-                        Ok(#subtype(#(#l_vars.#method_iter(#r_vars)),*))
+                        ::core::result::Result::Ok(#subtype(#(#exprs),*))
                     }
                 };
                 matches.push(matcher);
             }
-            VariantData::Struct(ref fields) => {
+            Fields::Named(ref fields) => {
                 // The patern that is outputted should look like this:
                 // (Subtype{a: __l_a, ...}, Subtype{a: __r_a, ...} => {
                 //     Ok(Subtype{a: __l_a.add(__r_a), ...})
                 // }
-                let size = fields.len();
+                let size = fields.named.len();
                 let field_names: &Vec<_> =
-                    &fields.iter().map(|f| f.ident.as_ref().unwrap()).collect();
+                    &fields.named.iter().map(|f| f.ident.as_ref().unwrap()).collect();
                 let l_vars = &numbered_vars(size, "l_");
                 let r_vars = &numbered_vars(size, "r_");
-                let method_iter = method_iter.by_ref();
+                let exprs = var_exprs(&fields.named, attrs, l_vars, r_vars, method_ident);
                 let matcher = quote!{
                     (#subtype{#(#field_names: #l_vars),*},
                      #subtype{#(#field_names: #r_vars),*}) => {
-                        Ok(#subtype{#(#field_names: #l_vars.#method_iter(#r_vars)),*})
+                        ::core::result::Result::Ok(#subtype{#(#field_names: #exprs),*})
                     }
                 };
                 matches.push(matcher);
             }
-            VariantData::Unit => {
-                let message = format!("Cannot {}() unit variants", method_ident.to_string());
-                matches.push(quote!((#subtype, #subtype) => Err(#message)));
+            Fields::Unit => {
+                let message = format!("Cannot {}() unit variants", method_ident);
+                matches.push(quote!(
+                    (#subtype, #subtype) => ::core::result::Result::Err(#message)
+                ));
             }
         }
     }
@@ -128,8 +182,8 @@ fn enum_content(input_type: &Ident, variants: &Vec<Variant>, method_ident: &Iden
     if variants.len() > 1 {
         // In the strange case where there's only one enum variant this is would be an unreachable
         // match.
-        let message = format!("Trying to {} mismatched enum variants", method_ident.to_string());
-        matches.push(quote!(_ => Err(#message)));
+        let message = format!("Trying to {} mismatched enum variants", method_ident);
+        matches.push(quote!(_ => ::core::result::Result::Err(#message)));
     }
     quote!(
         match (self, rhs) {
@@ -137,3 +191,40 @@ fn enum_content(input_type: &Ident, variants: &Vec<Variant>, method_ident: &Iden
         }
     )
 }
+
+/// Generates `__l_0.add(__r_0)` for every field of a variant
+fn var_exprs(fields: &Punctuated<Field, Comma>,
+             attrs: &Attrs,
+             l_vars: &[Ident],
+             r_vars: &[Ident],
+             method_ident: &Ident)
+             -> Vec<TokenStream> {
+    fields.iter()
+        .zip(l_vars.iter().zip(r_vars))
+        .map(|(field, (l_var, r_var))| field_expr(field, attrs, l_var, r_var, method_ident))
+        .collect()
+}
+
+/// Combines the left and right hand side of a single field.
+/// `PhantomData` is rebuilt, a skipped field keeps the left hand side and a field with a `with`
+/// option is passed to that function instead of the trait method.
+fn field_expr<L, R>(field: &Field,
+                    attrs: &Attrs,
+                    lhs: L,
+                    rhs: R,
+                    method_ident: &Ident)
+                    -> TokenStream
+    where L: ToTokens,
+          R: ToTokens
+{
+    let options = attrs.field(field);
+    if is_phantom_data(&field.ty) {
+        quote!(::core::marker::PhantomData)
+    } else if options.flag("skip") {
+        quote!(#lhs)
+    } else if let Some(with) = options.path("with") {
+        quote!(#with(#lhs, #rhs))
+    } else {
+        quote!(#lhs.#method_ident(#rhs))
+    }
+}
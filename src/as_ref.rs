@@ -0,0 +1,121 @@
+use proc_macro2::{Span, TokenStream};
+use syn::{Data, DeriveInput, Field, Fields, GenericParam, Ident, Member};
+use crate::attrs::{Attrs, Kind, Position, Spec};
+use crate::diagnostics::{self, Error};
+use crate::utils::{add_extra_generic_param, add_extra_where_clauses, bounds, field_member};
+
+/// `#[as_ref]` picks the fields to give references to and `forward` gives a reference to
+/// everything the field itself can be referenced as. `AsMut` understands the options of `AsRef`
+/// as well, so they stay in sync.
+const SPEC: Spec = Spec {
+    type_keys: &[("bound", Kind::Str), ("forward", Kind::Flag)],
+    variant_keys: &[],
+    field_keys: &[("forward", Kind::Flag)],
+    markers: &[Position::Field],
+    shared: Some("as_ref"),
+};
+
+/// Provides the hook to expand `#[derive(AsRef)]` and `#[derive(AsMut)]` into an implementation
+/// of the trait for each of the selected fields
+pub fn expand(input: &DeriveInput, trait_name: &str) -> Result<TokenStream, Error> {
+    let trait_ident = Ident::new(trait_name, Span::call_site());
+    let input_type = &input.ident;
+    let spec = match trait_name {
+        "AsRef" => Spec { shared: None, ..SPEC },
+        _ => SPEC,
+    };
+    let attrs = Attrs::parse(input, trait_name, &spec);
+    let fields = match input.data {
+        Data::Struct(ref data) => Ok(&data.fields),
+        Data::Enum(ref data) => {
+            Err(diagnostics::unsupported_enum(trait_name, data.enum_token, input_type))
+        }
+        Data::Union(ref data) => Err(diagnostics::unsupported_union(trait_name, data.union_token)),
+    };
+    let (attrs, fields) = diagnostics::join(attrs, fields)?;
+    let attrs = &attrs;
+    let fields = selected_fields(input, attrs, fields, trait_name);
+    let (bounds, fields) = diagnostics::join(bounds(&attrs.ty), fields)?;
+    let (method_ident, reference) = match trait_name {
+        "AsRef" => (Ident::new("as_ref", Span::call_site()), quote!(&)),
+        _ => (Ident::new("as_mut", Span::call_site()), quote!(&mut)),
+    };
+    let (_, ty_generics, _) = input.generics.split_for_impl();
+
+    let mut tokens = TokenStream::new();
+    for (member, field, forward) in fields {
+        let field_ty = &field.ty;
+        let mut predicates = bounds.clone().unwrap_or_default();
+        let (generics, target, expr) = if forward {
+            // Every type the field can be referenced as gets its own impl through a type
+            // parameter.
+            let as_ident = Ident::new("__AsT", Span::call_site());
+            let mut generics = add_extra_generic_param(&input.generics, &as_ident);
+            if let Some(GenericParam::Type(ref mut param)) = generics.params.last_mut() {
+                param.bounds.push(parse_quote!(?::core::marker::Sized));
+            }
+            if bounds.is_none() {
+                predicates.push(parse_quote!(#field_ty: ::core::convert::#trait_ident<#as_ident>));
+            }
+            (generics,
+             quote!(#as_ident),
+             quote!(::core::convert::#trait_ident::#method_ident(#reference self.#member)))
+        } else {
+            (input.generics.clone(), quote!(#field_ty), quote!(#reference self.#member))
+        };
+        let generics = add_extra_where_clauses(&generics, predicates);
+        let (impl_generics, _, where_clause) = generics.split_for_impl();
+        tokens.extend(quote! {
+            impl #impl_generics ::core::convert::#trait_ident<#target> for #input_type #ty_generics
+                #where_clause
+            {
+                fn #method_ident(#reference self) -> #reference #target {
+                    #expr
+                }
+            }
+        });
+    }
+    Ok(tokens)
+}
+
+/// Returns the only field of a newtype, or otherwise the ones that are marked with `#[as_ref]`,
+/// together with whether they forward
+fn selected_fields<'a>(input: &DeriveInput,
+                       attrs: &Attrs,
+                       fields: &'a Fields,
+                       trait_name: &str)
+                       -> Result<Vec<(Member, &'a Field, bool)>, Error> {
+    let single = fields.len() == 1;
+    let mut selected: Vec<(Member, &Field, bool)> = vec![];
+    let mut errors = vec![];
+    for (i, field) in fields.iter().enumerate() {
+        let options = attrs.field(field);
+        if !single && !options.marked() {
+            continue;
+        }
+        let member = field_member(i, field);
+        let forward = attrs.ty.flag("forward") || options.flag("forward");
+        // Forwarding covers any type, so it would overlap with the impls for other fields
+        let conflict = if !selected.is_empty() && (forward || selected.iter().any(|s| s.2)) {
+            Some("only one field can be marked with #[as_ref] when forwarding")
+        } else if selected.iter().any(|s| s.1.ty == field.ty) {
+            Some("another field marked with #[as_ref] has the same type")
+        } else {
+            None
+        };
+        if let Some(message) = conflict {
+            errors.push(Error::new_spanned(field, message));
+            continue;
+        }
+        selected.push((member, field, forward));
+    }
+    diagnostics::collect(errors.into_iter().map(Err::<(), _>))?;
+    if selected.is_empty() {
+        return Err(Error::new_spanned(&input.ident,
+                                      format!("derive({}) needs fields to give references to, \
+                                               either the only field or ones marked with \
+                                               #[as_ref]",
+                                              trait_name)));
+    }
+    Ok(selected)
+}
@@ -0,0 +1,344 @@
+//! The options that configure the derives.
+//!
+//! Every derive has its own attribute, named after its trait in snake case, like `#[add(...)]`,
+//! `#[bit_and(...)]` or `#[from(...)]`.
+//! The same options can also be given as `#[derive_more(Add(...))]`.
+//! Options directly inside `#[derive_more(...)]` apply to every derive of the type, so they have
+//! to be understood by all of them.
+//! Options can be put on the type, on the variants of an enum and on fields.
+//! Each derive declares which options it understands at every one of these places, so an unknown
+//! or misplaced option results in an error that points at it:
+//!
+//! ```compile_fail
+//! # #[macro_use] extern crate derive_more;
+//! #[derive(Add)]
+//! struct MyInts(#[add(bound = "i32: Copy")] i32, i32);
+//! # fn main() {}
+//! ```
+//!
+//! Some derives also accept their attribute without any options at certain positions, to mark a
+//! field or a variant, like `#[deref]`.
+
+use std::ptr;
+
+use syn::{Attribute, Data, DeriveInput, Expr, ExprLit, Field, Lit, LitStr, Meta, Path, Type,
+          Variant};
+use syn::punctuated::Punctuated;
+use crate::diagnostics::{self, Error};
+use crate::utils;
+
+/// The places an option can be put on
+#[derive(Clone, Copy, PartialEq)]
+pub enum Position {
+    Type,
+    Variant,
+    Field,
+}
+
+impl Position {
+    fn describe(self) -> &'static str {
+        match self {
+            Position::Type => "the type",
+            Position::Variant => "a variant",
+            Position::Field => "a field",
+        }
+    }
+}
+
+/// The kinds of values an option can have
+#[derive(Clone, Copy)]
+pub enum Kind {
+    /// An option without a value, like `skip`
+    Flag,
+    /// An option with a string as value, like `bound = "T: Copy"`
+    Str,
+    /// An option with the path of a function as value, like `with = "std::cmp::max"`
+    Path,
+    /// An option with a list of types, like `types(u8, ())`
+    Types,
+}
+
+type Items = Punctuated<Meta, Token![,]>;
+type Types = Punctuated<Type, Token![,]>;
+
+const POSITIONS: [Position; 3] = [Position::Type, Position::Variant, Position::Field];
+
+/// The options a derive understands at each position
+pub struct Spec {
+    pub type_keys: &'static [(&'static str, Kind)],
+    pub variant_keys: &'static [(&'static str, Kind)],
+    pub field_keys: &'static [(&'static str, Kind)],
+    /// The positions where the attribute can be used without options, like `#[deref]`
+    pub markers: &'static [Position],
+    /// The attribute of another derive whose options are understood as well, like `deref` for
+    /// `DerefMut`
+    pub shared: Option<&'static str>,
+}
+
+impl Spec {
+    fn keys(&self, position: Position) -> &'static [(&'static str, Kind)] {
+        match position {
+            Position::Type => self.type_keys,
+            Position::Variant => self.variant_keys,
+            Position::Field => self.field_keys,
+        }
+    }
+}
+
+/// The options that every derive understands
+pub const DEFAULT_SPEC: Spec = Spec {
+    type_keys: &[("bound", Kind::Str)],
+    variant_keys: &[],
+    field_keys: &[],
+    markers: &[],
+    shared: None,
+};
+
+/// The options given at one position
+#[derive(Default)]
+pub struct Options {
+    items: Vec<Meta>,
+    marked: bool,
+}
+
+impl Options {
+    fn all<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a Meta> + 'a {
+        self.items.iter().filter(move |item| item.path().is_ident(key))
+    }
+
+    /// Returns whether the flag `key` was given
+    pub fn flag(&self, key: &str) -> bool {
+        self.all(key).next().is_some()
+    }
+
+    /// Returns the path given as the option `key`, if any
+    pub fn path(&self, key: &str) -> Option<Path> {
+        self.strs(key).next().and_then(|path| path.parse().ok())
+    }
+
+    /// Returns the types listed in every occurrence of the option `key`, in the order they were
+    /// given
+    pub fn types(&self, key: &str) -> Vec<Type> {
+        self.all(key)
+            .filter_map(|item| match *item {
+                Meta::List(ref list) => list.parse_args_with(Types::parse_terminated).ok(),
+                _ => None,
+            })
+            .flatten()
+            .collect()
+    }
+
+    /// Returns whether the attribute of the derive itself was given, like `#[deref]` or
+    /// `#[deref(forward)]`
+    pub fn marked(&self) -> bool {
+        self.marked
+    }
+
+    /// Returns the string of every occurrence of the option `key`, in the order they were given
+    pub fn strs<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a LitStr> + 'a {
+        self.all(key).filter_map(|item| str_value(item).ok())
+    }
+}
+
+/// The options of a type, its variants and its fields, after checking that the derive understands
+/// all of them
+pub struct Attrs<'a> {
+    /// The options on the type itself
+    pub ty: Options,
+    variants: Vec<(&'a Variant, Options)>,
+    fields: Vec<(&'a Field, Options)>,
+}
+
+impl<'a> Attrs<'a> {
+    pub fn parse(input: &'a DeriveInput,
+                 trait_name: &str,
+                 spec: &Spec)
+                 -> Result<Attrs<'a>, Error> {
+        let mut namespaces = vec![namespace(trait_name)];
+        namespaces.extend(spec.shared.map(String::from));
+        let parser = Parser {
+            trait_name,
+            namespaces,
+            spec,
+        };
+        let ty = parser.parse(&input.attrs, Position::Type);
+        let variants = match input.data {
+            Data::Enum(ref data) => {
+                data.variants
+                    .iter()
+                    .map(|v| parser.parse(&v.attrs, Position::Variant).map(|o| (v, o)))
+                    .collect()
+            }
+            _ => vec![],
+        };
+        let fields = utils::all_fields(input)
+            .into_iter()
+            .map(|field| parser.parse(&field.attrs, Position::Field).map(|o| (field, o)));
+        let nested = diagnostics::join(diagnostics::collect(fields),
+                                       diagnostics::collect(variants));
+        let (ty, (fields, variants)) = diagnostics::join(ty, nested)?;
+        Ok(Attrs {
+            ty,
+            variants,
+            fields,
+        })
+    }
+
+    /// Returns the options on one of the variants of the enum
+    pub fn variant(&self, variant: &Variant) -> &Options {
+        self.variants
+            .iter()
+            .find(|&&(v, _)| ptr::eq(v, variant))
+            .map(|(_, options)| options)
+            .expect("variant of another type")
+    }
+
+    /// Returns the options on one of the fields of the type
+    pub fn field(&self, field: &Field) -> &Options {
+        self.fields
+            .iter()
+            .find(|&&(f, _)| ptr::eq(f, field))
+            .map(|(_, options)| options)
+            .expect("field of another type")
+    }
+}
+
+/// Returns the string of an option like `bound = "T: Copy"`
+fn str_value(item: &Meta) -> Result<&LitStr, Error> {
+    let value = match *item {
+        Meta::NameValue(ref name_value) => &name_value.value,
+        _ => {
+            let key = item.path().get_ident().map(ToString::to_string).unwrap_or_default();
+            return Err(Error::new_spanned(item, format!("expected `{} = \"...\"`", key)));
+        }
+    };
+    match *value {
+        Expr::Lit(ExprLit { lit: Lit::Str(ref lit), .. }) => Ok(lit),
+        _ => Err(Error::new_spanned(value, "expected a string literal")),
+    }
+}
+
+/// Returns the name of the attribute of a derive, like `add_assign` for `AddAssign`
+fn namespace(trait_name: &str) -> String {
+    let mut namespace = String::new();
+    for (i, c) in trait_name.chars().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            namespace.push('_');
+        }
+        namespace.extend(c.to_lowercase());
+    }
+    namespace
+}
+
+struct Parser<'a> {
+    trait_name: &'a str,
+    /// The attribute of the derive, followed by the shared one, if any
+    namespaces: Vec<String>,
+    spec: &'a Spec,
+}
+
+impl<'a> Parser<'a> {
+    fn parse(&self, attrs: &[Attribute], position: Position) -> Result<Options, Error> {
+        let mut items = vec![];
+        let mut marked = false;
+        for attr in attrs {
+            if let Some(namespace) = self.namespaces.iter().find(|n| attr.path().is_ident(n)) {
+                match attr.meta {
+                    Meta::List(_) => {
+                        items.extend(attr.parse_args_with(Items::parse_terminated)?);
+                    }
+                    Meta::Path(_) if self.spec.markers.contains(&position) => {}
+                    Meta::Path(_) if !self.spec.markers.is_empty() => {
+                        let allowed: Vec<_> =
+                            self.spec.markers.iter().map(|p| p.describe()).collect();
+                        return Err(Error::new_spanned(&attr.meta,
+                                                      format!("#[{}] can only be used on {}",
+                                                              namespace,
+                                                              allowed.join(" or "))));
+                    }
+                    _ => {
+                        return Err(Error::new_spanned(&attr.meta,
+                                                      format!("expected options like #[{}(...)]",
+                                                              namespace)));
+                    }
+                }
+                marked = true;
+            } else if attr.path().is_ident("derive_more") {
+                for item in attr.parse_args_with(Items::parse_terminated)? {
+                    match item {
+                        Meta::List(ref list) if is_trait_name(&list.path) => {
+                            if !crate::TRAIT_NAMES.iter().any(|name| list.path.is_ident(name)) {
+                                let name = list.path.get_ident().unwrap();
+                                return Err(Error::new_spanned(&list.path,
+                                                              format!("unknown derive `{}` in \
+                                                                       #[derive_more(...)]",
+                                                                      name)));
+                            }
+                            if list.path.is_ident(self.trait_name) {
+                                items.extend(list.parse_args_with(Items::parse_terminated)?);
+                            }
+                        }
+                        item => items.push(item),
+                    }
+                }
+            }
+        }
+        diagnostics::collect(items.iter().map(|item| self.check(item, position)))?;
+        Ok(Options { items, marked })
+    }
+
+    fn check(&self, item: &Meta, position: Position) -> Result<(), Error> {
+        let key = match item.path().get_ident() {
+            Some(ident) => ident.to_string(),
+            None => return Err(Error::new_spanned(item.path(), "expected the name of an option")),
+        };
+        if let Some(&(_, kind)) = self.spec.keys(position).iter().find(|k| k.0 == key) {
+            return match (kind, item) {
+                (Kind::Flag, &Meta::Path(_)) => Ok(()),
+                (Kind::Flag, _) => {
+                    Err(Error::new_spanned(item,
+                                           format!("expected just `{}`, without a value", key)))
+                }
+                (Kind::Str, _) => str_value(item).map(|_| ()),
+                (Kind::Types, Meta::List(list)) => {
+                    list.parse_args_with(Types::parse_terminated).map(|_| ())
+                }
+                (Kind::Types, _) => {
+                    Err(Error::new_spanned(item,
+                                           format!("expected a list of types like `{}(u8, ())`",
+                                                   key)))
+                }
+                (Kind::Path, _) => {
+                    let path = str_value(item)?;
+                    path.parse::<Path>()
+                        .map(|_| ())
+                        .map_err(|_| {
+                            Error::new(path.span(),
+                                       format!("expected a path like \"module::function\", \
+                                                found `{}`",
+                                               path.value()))
+                        })
+                }
+            };
+        }
+        let allowed: Vec<_> = POSITIONS.iter()
+            .filter(|p| self.spec.keys(**p).iter().any(|k| k.0 == key))
+            .map(|p| p.describe())
+            .collect();
+        let message = if allowed.is_empty() {
+            format!("unknown option `{}` for derive({})", key, self.trait_name)
+        } else {
+            format!("option `{}` of derive({}) can only be used on {}, not on {}",
+                    key,
+                    self.trait_name,
+                    allowed.join(" or "),
+                    position.describe())
+        };
+        Err(Error::new_spanned(item.path(), message))
+    }
+}
+
+/// Trait names are capitalized, while option names are in snake case
+fn is_trait_name(path: &syn::Path) -> bool {
+    path.get_ident().is_some_and(|ident| ident.to_string().starts_with(char::is_uppercase))
+}
@@ -0,0 +1,85 @@
+use proc_macro2::TokenStream;
+use syn::{Data, DeriveInput, Fields};
+use crate::attrs::{Attrs, Kind, Spec};
+use crate::diagnostics::{self, Error};
+use crate::utils::{add_extra_where_clauses, bounds, field_types_using_params, is_phantom_data,
+                   numbered_vars};
+
+/// `into` makes parameters take `impl Into<T>`, either for every field or a single one, and
+/// `skip` leaves a field out of the parameters and uses its default value instead
+const SPEC: Spec = Spec {
+    type_keys: &[("bound", Kind::Str), ("into", Kind::Flag)],
+    variant_keys: &[],
+    field_keys: &[("into", Kind::Flag), ("skip", Kind::Flag)],
+    markers: &[],
+    shared: None,
+};
+
+/// Provides the hook to expand `#[derive(Constructor)]` into a `new` function that takes a
+/// parameter for every field in declaration order
+pub fn expand(input: &DeriveInput, trait_name: &str) -> Result<TokenStream, Error> {
+    let input_type = &input.ident;
+    let attrs = Attrs::parse(input, trait_name, &SPEC);
+    let fields = match input.data {
+        Data::Struct(ref data) => Ok(&data.fields),
+        Data::Enum(ref data) => {
+            Err(diagnostics::unsupported_enum(trait_name, data.enum_token, input_type))
+        }
+        Data::Union(ref data) => Err(diagnostics::unsupported_union(trait_name, data.union_token)),
+    };
+    let (attrs, fields) = diagnostics::join(attrs, fields)?;
+    let attrs = &attrs;
+    let bounds = bounds(&attrs.ty)?;
+
+    let vars = numbered_vars(fields.len(), "");
+    let mut params = vec![];
+    let mut exprs = vec![];
+    let mut defaulted = vec![];
+    for (field, var) in fields.iter().zip(&vars) {
+        let options = attrs.field(field);
+        let field_ty = &field.ty;
+        let name = field.ident.as_ref().unwrap_or(var);
+        if is_phantom_data(field_ty) {
+            exprs.push(quote!(::core::marker::PhantomData));
+        } else if options.flag("skip") {
+            exprs.push(quote!(::core::default::Default::default()));
+            defaulted.push(field);
+        } else if attrs.ty.flag("into") || options.flag("into") {
+            params.push(quote!(#name: impl ::core::convert::Into<#field_ty>));
+            exprs.push(quote!(::core::convert::Into::into(#name)));
+        } else {
+            params.push(quote!(#name: #field_ty));
+            exprs.push(quote!(#name));
+        }
+    }
+    let body = match *fields {
+        Fields::Named(_) => {
+            // It's safe to unwrap because struct fields always have an identifier
+            let field_names = fields.iter().map(|f| f.ident.as_ref().unwrap());
+            quote!(#input_type{#(#field_names: #exprs),*})
+        }
+        Fields::Unnamed(_) => quote!(#input_type(#(#exprs),*)),
+        Fields::Unit => quote!(#input_type),
+    };
+
+    let predicates = match bounds {
+        Some(predicates) => predicates,
+        None => {
+            field_types_using_params(input, defaulted)
+                .into_iter()
+                .map(|ty| parse_quote!(#ty: ::core::default::Default))
+                .collect()
+        }
+    };
+    let generics = add_extra_where_clauses(&input.generics, predicates);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics #input_type #ty_generics #where_clause {
+            #[allow(clippy::too_many_arguments)]
+            pub fn new(#(#params),*) -> #input_type #ty_generics {
+                #body
+            }
+        }
+    })
+}
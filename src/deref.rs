@@ -0,0 +1,92 @@
+use proc_macro2::{Span, TokenStream};
+use syn::{Data, DeriveInput, Ident};
+use crate::attrs::{Attrs, Kind, Position, Spec};
+use crate::diagnostics::{self, Error};
+use crate::utils::{add_extra_where_clauses, bounds, field_types_using_params, marked_field};
+
+/// `#[deref]` picks the field to dereference to and `forward` dereferences to the target of that
+/// field instead. `DerefMut` understands the options of `Deref` as well, so they stay in sync.
+const SPEC: Spec = Spec {
+    type_keys: &[("bound", Kind::Str), ("forward", Kind::Flag)],
+    variant_keys: &[],
+    field_keys: &[("forward", Kind::Flag)],
+    markers: &[Position::Field],
+    shared: Some("deref"),
+};
+
+/// Provides the hook to expand `#[derive(Deref)]` and `#[derive(DerefMut)]` into an
+/// implementation of the trait
+pub fn expand(input: &DeriveInput, trait_name: &str) -> Result<TokenStream, Error> {
+    let trait_ident = Ident::new(trait_name, Span::call_site());
+    let input_type = &input.ident;
+    let spec = match trait_name {
+        "Deref" => Spec { shared: None, ..SPEC },
+        _ => SPEC,
+    };
+    let attrs = Attrs::parse(input, trait_name, &spec);
+    let fields = match input.data {
+        Data::Struct(ref data) => Ok(&data.fields),
+        Data::Enum(ref data) => {
+            Err(diagnostics::unsupported_enum(trait_name, data.enum_token, input_type))
+        }
+        Data::Union(ref data) => Err(diagnostics::unsupported_union(trait_name, data.union_token)),
+    };
+    let (attrs, fields) = diagnostics::join(attrs, fields)?;
+    let attrs = &attrs;
+    let field = marked_field(input, attrs, fields, trait_name, "deref");
+    let (bounds, (member, field)) = diagnostics::join(bounds(&attrs.ty), field)?;
+    let field_ty = &field.ty;
+    let forward = attrs.ty.flag("forward") || attrs.field(field).flag("forward");
+
+    let predicates = match bounds {
+        Some(predicates) => predicates,
+        None if forward => {
+            field_types_using_params(input, Some(field))
+                .into_iter()
+                .map(|ty| parse_quote!(#ty: ::core::ops::#trait_ident))
+                .collect()
+        }
+        None => vec![],
+    };
+    let generics = add_extra_where_clauses(&input.generics, predicates);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let body = match (trait_name, forward) {
+        ("Deref", false) => {
+            quote! {
+                type Target = #field_ty;
+                fn deref(&self) -> &Self::Target {
+                    &self.#member
+                }
+            }
+        }
+        ("Deref", true) => {
+            quote! {
+                type Target = <#field_ty as ::core::ops::Deref>::Target;
+                fn deref(&self) -> &Self::Target {
+                    ::core::ops::Deref::deref(&self.#member)
+                }
+            }
+        }
+        (_, false) => {
+            quote! {
+                fn deref_mut(&mut self) -> &mut Self::Target {
+                    &mut self.#member
+                }
+            }
+        }
+        (_, true) => {
+            quote! {
+                fn deref_mut(&mut self) -> &mut Self::Target {
+                    ::core::ops::DerefMut::deref_mut(&mut self.#member)
+                }
+            }
+        }
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::core::ops::#trait_ident for #input_type #ty_generics #where_clause {
+            #body
+        }
+    })
+}
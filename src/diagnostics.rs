@@ -0,0 +1,117 @@
+//! Errors that are reported to the user of a derive.
+//!
+//! Instead of panicking, which only results in an opaque "proc-macro derive panicked" message,
+//! the derives return an `Error`.
+//! This is turned into `compile_error!` invocations, so the user gets a normal compiler error
+//! that explains what is wrong with their type.
+//! Every error has the span of the enum, union, field or attribute that caused it, so the
+//! compiler points at the offending code.
+//! Multiple errors can be combined into one, so everything that is wrong with the input is
+//! reported in a single pass.
+//! For instance both the unknown option and the use of `Mul` on an enum are reported here:
+//!
+//! ```compile_fail
+//! # #[macro_use] extern crate derive_more;
+//! #[derive(Mul)]
+//! #[derive_more(bound = "T: Copy", unknown = "")]
+//! enum MixedInts {
+//!     SmallInt(i32),
+//!     BigInt(i64),
+//! }
+//! # fn main() {}
+//! ```
+
+use proc_macro2::{Span, TokenStream};
+use syn::Ident;
+
+pub use syn::Error;
+
+/// The error for a derive that can't be used on unions.
+pub fn unsupported_union(trait_name: &str, token: Token![union]) -> Error {
+    Error::new_spanned(token, format!("derive({}) cannot be used on unions", trait_name))
+}
+
+/// The error for a derive that can only be used on structs.
+pub fn unsupported_enum(trait_name: &str, token: Token![enum], input_type: &Ident) -> Error {
+    Error::new_spanned(token,
+                       format!("derive({}) cannot be used on enum `{}`, only on structs",
+                               trait_name,
+                               input_type))
+}
+
+/// The error for a derive that can only be used on enums.
+pub fn unsupported_struct(trait_name: &str, token: Token![struct], input_type: &Ident) -> Error {
+    Error::new_spanned(token,
+                       format!("derive({}) can only be used on enums, not on struct `{}`",
+                               trait_name,
+                               input_type))
+}
+
+/// The error for a derive that needs the fields of a struct.
+pub fn unsupported_unit_struct(trait_name: &str, input_type: &Ident) -> Error {
+    Error::new_spanned(input_type,
+                       format!("derive({}) cannot be used on unit struct `{}`",
+                               trait_name,
+                               input_type))
+}
+
+/// Generates a warning with the given span.
+/// Derives can't emit warnings on stable Rust, so this uses a unit struct that is marked as
+/// deprecated, with the message as its note.
+pub fn warning(span: Span, message: &str) -> TokenStream {
+    quote_spanned! {span=>
+        const _: () = {
+            #[deprecated(note = #message)]
+            struct Warning;
+            let _ = Warning;
+        };
+    }
+}
+
+/// Lists the names for a message that is about all of them, like "`A` and `B` both" or
+/// "`A`, `B` and `C` all".
+pub fn all_of(names: &[&Ident]) -> String {
+    let mut names: Vec<_> = names.iter().map(|name| format!("`{}`", name)).collect();
+    let last = names.pop().unwrap_or_default();
+    match names.len() {
+        0 => last,
+        1 => format!("{} and {} both", names[0], last),
+        _ => format!("{} and {} all", names.join(", "), last),
+    }
+}
+
+/// Combines the results of two independent checks, so the errors of both are reported if both
+/// failed.
+pub fn join<A, B>(a: Result<A, Error>, b: Result<B, Error>) -> Result<(A, B), Error> {
+    match (a, b) {
+        (Ok(a), Ok(b)) => Ok((a, b)),
+        (Err(error), Ok(_)) | (Ok(_), Err(error)) => Err(error),
+        (Err(mut error), Err(other)) => {
+            error.combine(other);
+            Err(error)
+        }
+    }
+}
+
+/// Collects the errors of a list of results, or returns all the values if there were none.
+pub fn collect<T, I>(results: I) -> Result<Vec<T>, Error>
+    where I: IntoIterator<Item = Result<T, Error>>
+{
+    let mut values = vec![];
+    let mut error: Option<Error> = None;
+    for result in results {
+        match result {
+            Ok(value) => values.push(value),
+            Err(other) => {
+                match error {
+                    Some(ref mut error) => error.combine(other),
+                    None => error = Some(other),
+                }
+            }
+        }
+    }
+    match error {
+        Some(error) => Err(error),
+        None => Ok(values),
+    }
+}
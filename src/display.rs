@@ -0,0 +1,297 @@
+use proc_macro2::{Span, TokenStream};
+use syn::{Data, DeriveInput, Fields, Ident, Index, LitStr, Path, WherePredicate};
+use crate::attrs::{Attrs, Kind, Options, Spec, DEFAULT_SPEC};
+use crate::diagnostics::{self, Error};
+use crate::utils::{add_extra_where_clauses, bounds, field_member, field_types_using_params};
+
+/// `fmt` gives the format string of a struct or a variant
+const SPEC: Spec = Spec {
+    type_keys: &[("bound", Kind::Str), ("fmt", Kind::Str)],
+    variant_keys: &[("fmt", Kind::Str)],
+    field_keys: DEFAULT_SPEC.field_keys,
+    markers: &[],
+    shared: None,
+};
+
+/// Provides the hook to expand `#[derive(Display)]` into an implementation of `Display`
+pub fn expand(input: &DeriveInput, trait_name: &str) -> Result<TokenStream, Error> {
+    let input_type = &input.ident;
+    let attrs = Attrs::parse(input, trait_name, &SPEC);
+    let shape = match input.data {
+        Data::Union(ref data) => Err(diagnostics::unsupported_union(trait_name, data.union_token)),
+        _ => Ok(()),
+    };
+    let (attrs, ()) = diagnostics::join(attrs, shape)?;
+    let attrs = &attrs;
+
+    let arms = match input.data {
+        Data::Struct(ref data) => {
+            let arm = arm(input, quote!(#input_type), &input.ident, &data.fields, &attrs.ty);
+            arm.map(|arm| vec![arm])
+        }
+        Data::Enum(ref data) => {
+            let type_fmt = match attrs.ty.strs("fmt").next() {
+                Some(fmt) => {
+                    Err(Error::new(fmt.span(),
+                                   format!("derive({}) on an enum takes the format string on \
+                                            each variant, not on the enum itself",
+                                           trait_name)))
+                }
+                None => Ok(()),
+            };
+            let arms = data.variants.iter().map(|variant| {
+                let variant_ident = &variant.ident;
+                arm(input,
+                    quote!(#input_type::#variant_ident),
+                    variant_ident,
+                    &variant.fields,
+                    attrs.variant(variant))
+            });
+            diagnostics::join(type_fmt, diagnostics::collect(arms)).map(|(_, arms)| arms)
+        }
+        Data::Union(_) => unreachable!(),
+    };
+
+    let (bounds, arms) = diagnostics::join(bounds(&attrs.ty), arms)?;
+    let predicates = match bounds {
+        Some(predicates) => predicates,
+        None => arms.iter().flat_map(|arm| arm.predicates.iter().cloned()).collect(),
+    };
+    let generics = add_extra_where_clauses(&input.generics, predicates);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let matches = arms.iter().map(|arm| &arm.tokens);
+    // A reference to an enum without variants isn't known to be empty, so the match is on the
+    // value behind it
+    let body = if arms.is_empty() {
+        quote!(match *self {})
+    } else {
+        quote!(match self { #(#matches),* })
+    };
+
+    Ok(quote!{
+        impl #impl_generics ::core::fmt::Display for #input_type #ty_generics #where_clause {
+            fn fmt(&self, __formatter: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                #body
+            }
+        }
+    })
+}
+
+struct Arm {
+    tokens: TokenStream,
+    /// The bounds for the field types that are formatted and use a type parameter
+    predicates: Vec<WherePredicate>,
+}
+
+/// Generates the match arm that formats a struct or a variant.
+/// With a format string only the fields it uses are bound, like `Type { x, 0: _0, .. }`.
+/// Without one a single field is formatted transparently and no fields means the name is written.
+fn arm(input: &DeriveInput,
+       path: TokenStream,
+       ident: &Ident,
+       fields: &Fields,
+       options: &Options)
+       -> Result<Arm, Error> {
+    if let Some(fmt) = options.strs("fmt").next() {
+        let names: Vec<_> = fields.iter()
+            .enumerate()
+            .map(|(i, field)| match field.ident {
+                Some(ref ident) => ident.to_string(),
+                None => format!("_{}", i),
+            })
+            .collect();
+        let format = parse_format(fmt, ident, &names)?;
+        let mut patterns = vec![];
+        let mut field_traits = vec![];
+        let mut counts = vec![];
+        for (i, (field, name)) in fields.iter().zip(&names).enumerate() {
+            let used: Vec<_> = format.args.iter().filter(|arg| arg.name == *name).collect();
+            if used.is_empty() {
+                continue;
+            }
+            // The bindings get the span of the format string, so it can capture them
+            let binding = Ident::new(name, fmt.span());
+            patterns.push(match field.ident {
+                Some(_) => quote!(#binding),
+                None => {
+                    let index = Index::from(i);
+                    quote!(#index: #binding)
+                }
+            });
+            // A width or precision has to be a `usize` instead of a reference to one, so it is
+            // passed as a named argument
+            if used.iter().any(|arg| arg.trait_path.is_none()) {
+                counts.push(binding);
+            }
+            let traits: Vec<_> = used.iter().filter_map(|arg| arg.trait_path.as_ref()).collect();
+            field_traits.push((field, traits));
+        }
+        let lit = LitStr::new(&format.string, fmt.span());
+        let mut predicates = vec![];
+        for (field, traits) in field_traits {
+            for ty in field_types_using_params(input, Some(field)) {
+                for trait_path in &traits {
+                    predicates.push(parse_quote!(#ty: #trait_path));
+                }
+            }
+        }
+        Ok(Arm {
+            tokens: quote!(#path { #(#patterns,)* .. } => {
+                ::core::write!(__formatter, #lit #(, #counts = *#counts)*)
+            }),
+            predicates,
+        })
+    } else if fields.len() == 1 {
+        let field = &fields.iter().next().unwrap();
+        let member = field_member(0, field);
+        let predicates = field_types_using_params(input, Some(*field))
+            .into_iter()
+            .map(|ty| parse_quote!(#ty: ::core::fmt::Display))
+            .collect();
+        Ok(Arm {
+            tokens: quote!(#path { #member: __0 } => {
+                ::core::fmt::Display::fmt(__0, __formatter)
+            }),
+            predicates,
+        })
+    } else if fields.is_empty() {
+        let name = ident.to_string();
+        Ok(Arm {
+            tokens: quote!(#path { .. } => __formatter.write_str(#name)),
+            predicates: vec![],
+        })
+    } else {
+        Err(Error::new_spanned(ident,
+                               format!("`{}` has more than one field, so it needs a format \
+                                        string like #[display(fmt = \"...\")]",
+                                       ident)))
+    }
+}
+
+struct Format {
+    /// The format string with positional arguments like `{0}` and `{}` renamed to the field at
+    /// that position, like `{_0}` or `{x}`
+    string: String,
+    args: Vec<Arg>,
+}
+
+/// An argument that is used by a format string
+struct Arg {
+    name: String,
+    /// The formatting trait, or `None` for a width or precision like `{:width$}`
+    trait_path: Option<Path>,
+}
+
+/// Finds the arguments in a format string and the formatting trait each of them is used with.
+/// Positional arguments are renamed to the field at that position, so they can be captured from
+/// a binding just like named ones.
+/// Implicit ones like `{}` refer to the fields in order, just like the arguments of `format!`.
+fn parse_format(fmt: &LitStr, ident: &Ident, fields: &[String]) -> Result<Format, Error> {
+    let field = |position: &str| {
+        position.parse()
+            .ok()
+            .and_then(|i: usize| fields.get(i))
+            .cloned()
+            .ok_or_else(|| {
+                Error::new(fmt.span(), format!("`{}` has no field {}", ident, position))
+            })
+    };
+    let value = fmt.value();
+    let mut string = String::new();
+    let mut args = vec![];
+    let mut next_position = 0;
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        string.push(c);
+        if c == '}' && chars.peek() == Some(&'}') {
+            string.push(chars.next().unwrap());
+        }
+        if c != '{' {
+            continue;
+        }
+        if chars.peek() == Some(&'{') {
+            string.push(chars.next().unwrap());
+            continue;
+        }
+        let mut placeholder = String::new();
+        loop {
+            match chars.next() {
+                Some('}') => break,
+                Some(c) => placeholder.push(c),
+                None => return Err(Error::new(fmt.span(), "unclosed `{` in format string")),
+            }
+        }
+        let (name, spec) = match placeholder.find(':') {
+            Some(i) => (placeholder[..i].trim(), &placeholder[i..]),
+            None => (placeholder.trim(), ""),
+        };
+        let name = if name.is_empty() {
+            next_position += 1;
+            field(&(next_position - 1).to_string())?
+        } else if name.chars().all(|c| c.is_ascii_digit()) {
+            field(name)?
+        } else {
+            name.to_string()
+        };
+        string.push_str(&name);
+        args.push(Arg {
+            name,
+            trait_path: Some(trait_path(spec)),
+        });
+        // Widths and precisions like `{:width$}` or `{:.1$}` are arguments as well
+        let mut count = String::new();
+        for c in spec.chars() {
+            match c {
+                '$' if !count.is_empty() => {
+                    let name = if count.chars().all(|c| c.is_ascii_digit()) {
+                        field(&count)?
+                    } else {
+                        count.clone()
+                    };
+                    string.push_str(&name);
+                    string.push('$');
+                    args.push(Arg {
+                        name,
+                        trait_path: None,
+                    });
+                    count.clear();
+                }
+                '*' if string.ends_with('.') => {
+                    return Err(Error::new(fmt.span(),
+                                          "a precision like `{:.*}` isn't supported, use one \
+                                           like `{:.1$}` or `{:.precision$}` instead"));
+                }
+                c if c.is_alphanumeric() || c == '_' => count.push(c),
+                c => {
+                    string.push_str(&count);
+                    string.push(c);
+                    count.clear();
+                }
+            }
+        }
+        string.push_str(&count);
+        string.push('}');
+    }
+    Ok(Format { string, args })
+}
+
+/// Returns the formatting trait for the spec of an argument, like `::core::fmt::LowerHex` for
+/// `:#x`
+fn trait_path(spec: &str) -> Path {
+    let name = if spec.ends_with('?') {
+        "Debug"
+    } else {
+        match spec.chars().last() {
+            Some('x') => "LowerHex",
+            Some('X') => "UpperHex",
+            Some('o') => "Octal",
+            Some('b') => "Binary",
+            Some('e') => "LowerExp",
+            Some('E') => "UpperExp",
+            Some('p') => "Pointer",
+            _ => "Display",
+        }
+    };
+    let name = Ident::new(name, Span::call_site());
+    parse_quote!(::core::fmt::#name)
+}
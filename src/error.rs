@@ -0,0 +1,165 @@
+use proc_macro2::TokenStream;
+use syn::{Data, DeriveInput, Field, Fields, GenericArgument, Member, PathArguments, Type};
+use crate::attrs::{Attrs, Kind, Spec, DEFAULT_SPEC};
+use crate::diagnostics::{self, Error};
+use crate::utils::{add_extra_where_clauses, bounds, field_member, types_using_params};
+
+/// `source` marks the field that is returned by `Error::source()` and `ignore` keeps a field named
+/// `source` from being used
+const SPEC: Spec = Spec {
+    type_keys: DEFAULT_SPEC.type_keys,
+    variant_keys: &[],
+    field_keys: &[("source", Kind::Flag), ("ignore", Kind::Flag)],
+    markers: &[],
+    shared: None,
+};
+
+/// Provides the hook to expand `#[derive(Error)]` into an implementation of `Error`
+pub fn expand(input: &DeriveInput, trait_name: &str) -> Result<TokenStream, Error> {
+    let input_type = &input.ident;
+    let attrs = Attrs::parse(input, trait_name, &SPEC);
+    let shape = match input.data {
+        Data::Union(ref data) => Err(diagnostics::unsupported_union(trait_name, data.union_token)),
+        _ => Ok(()),
+    };
+    let (attrs, ()) = diagnostics::join(attrs, shape)?;
+    let attrs = &attrs;
+
+    let sources = match input.data {
+        Data::Struct(ref data) => {
+            source_field(attrs, &data.fields).map(|source| vec![(quote!(#input_type), source)])
+        }
+        Data::Enum(ref data) => {
+            let sources = data.variants.iter().map(|variant| {
+                let variant_ident = &variant.ident;
+                source_field(attrs, &variant.fields)
+                    .map(|source| (quote!(#input_type::#variant_ident), source))
+            });
+            diagnostics::collect(sources)
+        }
+        Data::Union(_) => unreachable!(),
+    };
+    let (bounds, sources) = diagnostics::join(bounds(&attrs.ty), sources)?;
+
+    let mut arms = vec![];
+    let mut source_fields = vec![];
+    let mut without_source = false;
+    for (path, source) in sources {
+        match source {
+            Some((member, field)) => {
+                let (expr, error_ty) = source_expr(&field.ty);
+                arms.push(quote!(#path { #member: __source, .. } => #expr));
+                source_fields.push(error_ty);
+            }
+            None => without_source = true,
+        }
+    }
+
+    let predicates = match bounds {
+        Some(predicates) => predicates,
+        None => {
+            let (_, ty_generics, _) = input.generics.split_for_impl();
+            let mut predicates = vec![];
+            if !input.generics.params.is_empty() {
+                // Error requires Debug and Display, which might have bounds of their own
+                predicates.push(parse_quote!(
+                    #input_type #ty_generics: ::core::fmt::Debug + ::core::fmt::Display
+                ));
+            }
+            for ty in types_using_params(input, source_fields) {
+                predicates.push(parse_quote!(#ty: ::core::error::Error + 'static));
+            }
+            predicates
+        }
+    };
+    let generics = add_extra_where_clauses(&input.generics, predicates);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let source = if arms.is_empty() {
+        quote!()
+    } else {
+        if without_source {
+            arms.push(quote!(_ => ::core::option::Option::None));
+        }
+        quote! {
+            fn source(&self) -> ::core::option::Option<&(dyn ::core::error::Error + 'static)> {
+                match self {
+                    #(#arms),*
+                }
+            }
+        }
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::core::error::Error for #input_type #ty_generics #where_clause {
+            #source
+        }
+    })
+}
+
+/// Returns the field that is marked with `#[error(source)]`, or otherwise the one named `source`
+/// unless it is marked with `#[error(ignore)]`
+fn source_field<'a>(attrs: &Attrs,
+                    fields: &'a Fields)
+                    -> Result<Option<(Member, &'a Field)>, Error> {
+    let mut marked = vec![];
+    let mut named = None;
+    for (i, field) in fields.iter().enumerate() {
+        let member = field_member(i, field);
+        let options = attrs.field(field);
+        if options.flag("source") {
+            marked.push((member, field));
+        } else if !options.flag("ignore") &&
+                  field.ident.as_ref().is_some_and(|ident| ident == "source") {
+            named = Some((member, field));
+        }
+    }
+    if marked.len() > 1 {
+        let (_, field) = marked[1];
+        return Err(Error::new_spanned(field,
+                                      "only one field can be marked with #[error(source)]"));
+    }
+    Ok(marked.pop().or(named))
+}
+
+/// Returns the expression that turns the `__source` binding into the result of `source()`, and
+/// the type that has to implement `Error`.
+/// An `Option` is `None` when there is no source and a `Box` is dereferenced, because a boxed
+/// `dyn Error` doesn't implement `Error` itself.
+fn source_expr(ty: &Type) -> (TokenStream, &Type) {
+    match generic_arg(ty, "Option") {
+        Some(inner) => {
+            let (error_ty, reference) = as_dyn_error(inner, quote!(__source));
+            (quote!(__source.as_ref().map(|__source| #reference)), error_ty)
+        }
+        None => {
+            let (error_ty, reference) = as_dyn_error(ty, quote!(__source));
+            (quote!(::core::option::Option::Some(#reference)), error_ty)
+        }
+    }
+}
+
+/// Converts a reference to the type into a `&(dyn Error + 'static)`, going through any boxes
+fn as_dyn_error(ty: &Type, reference: TokenStream) -> (&Type, TokenStream) {
+    match generic_arg(ty, "Box") {
+        Some(inner) => as_dyn_error(inner, quote!(&**#reference)),
+        None => (ty, quote!(#reference as &(dyn ::core::error::Error + 'static))),
+    }
+}
+
+/// Returns the type argument of a type like `Box<T>`, if the type has the given name
+fn generic_arg<'a>(ty: &'a Type, name: &str) -> Option<&'a Type> {
+    let segment = match *ty {
+        Type::Path(ref ty) if ty.qself.is_none() => ty.path.segments.last()?,
+        _ => return None,
+    };
+    match segment.arguments {
+        PathArguments::AngleBracketed(ref arguments) if segment.ident == name => {
+            match arguments.args.first() {
+                Some(GenericArgument::Type(ref ty)) if arguments.args.len() == 1 => Some(ty),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
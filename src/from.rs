@@ -1,49 +1,85 @@
-use std::collections::HashMap;
-
-use quote::{Tokens, ToTokens};
-use syn::{Body, Field, Ident, Variant, VariantData, MacroInput, Ty};
-use utils::{numbered_vars, number_idents};
+use proc_macro2::TokenStream;
+use syn::{Data, DataStruct, DeriveInput, Field, Fields, Generics, Type, Variant};
+use syn::punctuated::Punctuated;
+use syn::token::Comma;
+use crate::attrs::{Attrs, Kind, Position, Spec, DEFAULT_SPEC};
+use crate::diagnostics::{self, Error};
+use crate::utils::{add_extra_where_clauses, bounds, field_member, number_idents, payload_types};
 
+/// `#[from]` picks the variant to convert into when several variants hold the same type,
+/// `#[from(ignore)]` leaves a variant out and `#[from(types(...))]` lists the types a unit
+/// variant is converted from
+const SPEC: Spec = Spec {
+    type_keys: DEFAULT_SPEC.type_keys,
+    variant_keys: &[("ignore", Kind::Flag), ("types", Kind::Types)],
+    field_keys: &[],
+    markers: &[Position::Variant],
+    shared: None,
+};
 
 /// Provides the hook to expand `#[derive(From)]` into an implementation of `From`
-pub fn expand(input: &MacroInput, _: &str) -> Tokens {
-    let input_type = &input.ident;
-    match input.body {
-        Body::Struct(VariantData::Tuple(ref fields)) => {
-            if fields.len() == 1 {
-                newtype_from(input_type, &fields[0].ty)
-            } else {
-                tuple_from(input_type, fields)
-            }
+pub fn expand(input: &DeriveInput, trait_name: &str) -> Result<TokenStream, Error> {
+    let attrs = Attrs::parse(input, trait_name, &SPEC);
+    let shape = match input.data {
+        Data::Struct(DataStruct { fields: Fields::Unit, .. }) => {
+            Err(diagnostics::unsupported_unit_struct(trait_name, &input.ident))
         }
-        Body::Struct(VariantData::Struct(ref fields)) => {
-            if fields.len() == 1 {
-                newtype_struct_from(input_type, &fields[0])
-            } else {
-                struct_from(input_type, fields)
+        Data::Union(ref data) => Err(diagnostics::unsupported_union(trait_name, data.union_token)),
+        _ => Ok(()),
+    };
+    let (attrs, ()) = diagnostics::join(attrs, shape)?;
+    let predicates = bounds(&attrs.ty)?.unwrap_or_default();
+    let generics = &add_extra_where_clauses(&input.generics, predicates);
+    let tokens = match input.data {
+        Data::Struct(ref data) => {
+            match data.fields {
+                Fields::Unnamed(ref fields) => {
+                    if fields.unnamed.len() == 1 {
+                        newtype_from(input, generics, &fields.unnamed[0].ty)
+                    } else {
+                        tuple_from(input, generics, &fields.unnamed)
+                    }
+                }
+                Fields::Named(ref fields) => {
+                    if fields.named.len() == 1 {
+                        newtype_struct_from(input, generics, &fields.named[0])
+                    } else {
+                        struct_from(input, generics, &fields.named)
+                    }
+                }
+                Fields::Unit => unreachable!(),
             }
         }
-        Body::Enum(ref variants) => enum_from(input_type, variants),
-        _ => panic!("Only tuple structs and enums can derive From"),
-    }
+        Data::Enum(ref data) => enum_from(input, &attrs, generics, &data.variants)?,
+        Data::Union(_) => unreachable!(),
+    };
+    Ok(tokens)
 }
 
-fn newtype_from(input_type: &Ident, original_type: &Ty) -> Tokens {
+fn newtype_from(input: &DeriveInput, generics: &Generics, original_type: &Type) -> TokenStream {
+    let input_type = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
     quote!{
-        impl ::std::convert::From<#original_type> for #input_type {
-            fn from(original: #original_type) -> #input_type {
+        impl #impl_generics ::core::convert::From<#original_type> for #input_type #ty_generics
+            #where_clause
+        {
+            fn from(original: #original_type) -> #input_type #ty_generics {
                 #input_type(original)
             }
         }
     }
 }
 
-fn newtype_struct_from(input_type: &Ident, field: &Field) -> Tokens {
+fn newtype_struct_from(input: &DeriveInput, generics: &Generics, field: &Field) -> TokenStream {
+    let input_type = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
     let field_name = &field.ident;
     let field_ty = &field.ty;
     quote!{
-        impl ::std::convert::From<#field_ty> for #input_type {
-            fn from(original: #field_ty) -> #input_type {
+        impl #impl_generics ::core::convert::From<#field_ty> for #input_type #ty_generics
+            #where_clause
+        {
+            fn from(original: #field_ty) -> #input_type #ty_generics {
                 #input_type{#field_name: original}
             }
         }
@@ -51,68 +87,143 @@ fn newtype_struct_from(input_type: &Ident, field: &Field) -> Tokens {
 }
 
 
-fn tuple_from<T: ToTokens>(input_type: &T, fields: &Vec<Field>) -> Tokens {
+fn tuple_from(input: &DeriveInput,
+              generics: &Generics,
+              fields: &Punctuated<Field, Comma>)
+              -> TokenStream {
+    let input_type = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
     let field_names = &number_idents(fields.len());
     let types: &Vec<_> = &fields.iter().map(|f| &f.ty).collect();
     quote!{
-        impl ::std::convert::From<(#(#types),*)> for #input_type {
-            fn from(original: (#(#types),*)) -> #input_type {
+        impl #impl_generics ::core::convert::From<(#(#types),*)> for #input_type #ty_generics
+            #where_clause
+        {
+            fn from(original: (#(#types),*)) -> #input_type #ty_generics {
                 #input_type(#(original.#field_names),*)
             }
         }
     }
 }
 
-fn struct_from<T: ToTokens>(input_type: &T, fields: &Vec<Field>) -> Tokens {
+fn struct_from(input: &DeriveInput,
+               generics: &Generics,
+               fields: &Punctuated<Field, Comma>)
+               -> TokenStream {
+    let input_type = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
     let argument_field_names = &number_idents(fields.len());
     let types: &Vec<_> = &fields.iter().map(|f| &f.ty).collect();
     let field_names: &Vec<_> = &fields.iter().map(|f| f.ident.as_ref().unwrap()).collect();
     quote!{
-        impl ::std::convert::From<(#(#types),*)> for #input_type {
-            fn from(original: (#(#types),*)) -> #input_type {
+        impl #impl_generics ::core::convert::From<(#(#types),*)> for #input_type #ty_generics
+            #where_clause
+        {
+            fn from(original: (#(#types),*)) -> #input_type #ty_generics {
                 #input_type{#(#field_names: original.#argument_field_names),*}
             }
         }
     }
 }
 
-fn enum_from(enum_ident: &Ident, variants: &Vec<Variant>) -> Tokens {
-    let mut types = vec![];
-    let mut idents = vec![];
-    let mut type_counts = HashMap::new();
+fn enum_from(input: &DeriveInput,
+             attrs: &Attrs,
+             generics: &Generics,
+             variants: &Punctuated<Variant, Comma>)
+             -> Result<TokenStream, Error> {
+    let enum_ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let mut errors = vec![];
+    // Variants are grouped by the type they convert from, so a variant with multiple fields and
+    // one with a tuple of the same types end up in the same group
+    let mut groups: Vec<(Type, Vec<_>)> = vec![];
 
-    for variant in variants {
-        match variant.data {
-            VariantData::Tuple(ref structs) => {
-                if structs.len() == 1 {
-                    let ty = &structs[0].ty;
-                    idents.push(&variant.ident);
-                    types.push(ty);
-                    let counter = type_counts.entry(ty).or_insert(0);
-                    *counter += 1;
-                }
+    // Unit variants hold nothing, so they are only converted from the types they list with
+    // `#[from(types(...))]`, or from `()` with a bare `#[from]`
+    let listed_types: Vec<_> = variants.iter().map(|v| attrs.variant(v).types("types")).collect();
+    let unit_type: Type = parse_quote!(());
+    for (variant, listed) in variants.iter().zip(&listed_types) {
+        let options = attrs.variant(variant);
+        if options.flag("ignore") {
+            continue;
+        }
+        // Listing types isn't a pick, only a bare `#[from]` is
+        let chosen = options.marked() && listed.is_empty();
+        let keys: Vec<Vec<&Type>> = if !variant.fields.is_empty() {
+            if !listed.is_empty() {
+                errors.push(Error::new_spanned(&variant.ident,
+                                               "#[from(types(...))] can only be used on unit \
+                                                variants"));
+                continue;
+            }
+            vec![variant.fields.iter().map(|f| &f.ty).collect()]
+        } else if !listed.is_empty() {
+            listed.iter().map(|ty| vec![ty]).collect()
+        } else if chosen {
+            vec![vec![&unit_type]]
+        } else {
+            vec![]
+        };
+        for field_types in keys {
+            // Variants with multiple fields are converted from a tuple, just like structs
+            let (old_type, _, _) = payload_types(&field_types);
+            let key: Type = parse_quote!(#old_type);
+            match groups.iter_mut().find(|group| group.0 == key) {
+                Some(group) => group.1.push((variant, chosen, old_type)),
+                None => groups.push((key, vec![(variant, chosen, old_type)])),
             }
-            _ => {}
         }
     }
 
-    let mut tokens = Tokens::new();
-
-    for (ident, old_type) in idents.iter().zip(types) {
-        if *type_counts.get(&old_type).unwrap() != 1 {
-            // If more than one newtype is present don't add automatic From, since it is
-            // ambiguous.
+    let mut tokens = TokenStream::new();
+    for (_, candidates) in &groups {
+        let chosen: Vec<_> = candidates.iter().filter(|c| c.1).collect();
+        let old_type = &candidates[0].2;
+        let variant = if candidates.len() == 1 {
+            candidates[0].0
+        } else if chosen.len() == 1 {
+            chosen[0].0
+        } else if chosen.len() > 1 {
+            errors.push(Error::new_spanned(&chosen[1].0.ident,
+                                           format!("only one variant converting from `{}` can \
+                                                    be marked with #[from]",
+                                                   old_type)));
             continue;
-        }
+        } else {
+            // If more than one variant converts from the same type don't add automatic From,
+            // since it is ambiguous, but tell the user how to pick one.
+            let names: Vec<_> = candidates.iter().map(|c| &c.0.ident).collect();
+            let message = format!("no `From<{}>` is generated for `{}`, because {} convert from \
+                                   it; mark one of them with #[from], or the others with \
+                                   #[from(ignore)]",
+                                  old_type,
+                                  enum_ident,
+                                  diagnostics::all_of(&names));
+            tokens.extend(diagnostics::warning(candidates[0].0.ident.span(), &message));
+            continue;
+        };
 
-        tokens.append(&quote!(
-            impl ::std::convert::From<#old_type> for #enum_ident {
-                fn from(original: #old_type) -> #enum_ident {
-                    #enum_ident::#ident(original)
+        let ident = &variant.ident;
+        let members = variant.fields.iter().enumerate().map(|(i, f)| field_member(i, f));
+        let values = match variant.fields.len() {
+            1 => vec![quote!(original)],
+            len => number_idents(len).into_iter().map(|i| quote!(original.#i)).collect(),
+        };
+        let param = if variant.fields.is_empty() {
+            quote!(_)
+        } else {
+            quote!(original)
+        };
+        tokens.extend(quote!(
+            impl #impl_generics ::core::convert::From<#old_type> for #enum_ident #ty_generics
+                #where_clause
+            {
+                fn from(#param: #old_type) -> #enum_ident #ty_generics {
+                    #enum_ident::#ident { #(#members: #values),* }
                 }
             }
-        )
-            .to_string())
+        ))
     }
-    tokens
+    diagnostics::collect(errors.into_iter().map(Err::<(), _>))?;
+    Ok(tokens)
 }
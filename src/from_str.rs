@@ -0,0 +1,215 @@
+use proc_macro2::{Span, TokenStream};
+use syn::{DataEnum, Data, DataStruct, DeriveInput, Field, Fields, Ident, LitStr};
+use crate::attrs::{Attrs, Kind, Spec};
+use crate::diagnostics::{self, Error};
+use crate::utils::{add_field_bounds, rename};
+
+/// `rename_all` and `case_insensitive` change how the variants of an enum are matched and `alias`
+/// adds extra names for a variant
+const SPEC: Spec = Spec {
+    type_keys: &[("bound", Kind::Str),
+                 ("rename_all", Kind::Str),
+                 ("case_insensitive", Kind::Flag)],
+    variant_keys: &[("alias", Kind::Str)],
+    field_keys: &[],
+    markers: &[],
+    shared: None,
+};
+
+/// The policies that `rename_all` accepts
+const RENAME_RULES: &[&str] = &["lowercase",
+                                "UPPERCASE",
+                                "PascalCase",
+                                "camelCase",
+                                "snake_case",
+                                "SCREAMING_SNAKE_CASE",
+                                "kebab-case",
+                                "SCREAMING-KEBAB-CASE"];
+
+/// Provides the hook to expand `#[derive(FromStr)]` into an implementation of `FromStr`
+pub fn expand(input: &DeriveInput, trait_name: &str) -> Result<TokenStream, Error> {
+    let attrs = Attrs::parse(input, trait_name, &SPEC);
+    let shape = match input.data {
+        Data::Struct(DataStruct { fields: Fields::Unit, .. }) => {
+            Err(diagnostics::unsupported_unit_struct(trait_name, &input.ident))
+        }
+        Data::Struct(ref data) if data.fields.len() != 1 => {
+            Err(Error::new_spanned(&input.ident,
+                                   format!("derive({}) can only be used on structs with a single \
+                                            field",
+                                           trait_name)))
+        }
+        Data::Union(ref data) => Err(diagnostics::unsupported_union(trait_name, data.union_token)),
+        _ => Ok(()),
+    };
+    let (attrs, ()) = diagnostics::join(attrs, shape)?;
+    let attrs = &attrs;
+    match input.data {
+        Data::Struct(_) if attrs.ty.flag("case_insensitive") => {
+            Err(enum_only(attrs, "case_insensitive", trait_name))
+        }
+        Data::Struct(_) if attrs.ty.strs("rename_all").next().is_some() => {
+            Err(enum_only(attrs, "rename_all", trait_name))
+        }
+        Data::Struct(ref data) => {
+            newtype_from_str(input, attrs, data.fields.iter().next().unwrap())
+        }
+        Data::Enum(ref data) => enum_from_str(input, attrs, data, trait_name),
+        Data::Union(_) => unreachable!(),
+    }
+}
+
+fn newtype_from_str(input: &DeriveInput,
+                    attrs: &Attrs,
+                    field: &Field)
+                    -> Result<TokenStream, Error> {
+    let input_type = &input.ident;
+    let generics = add_field_bounds(input, attrs, |_| quote!(::core::str::FromStr))?;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let field_ty = &field.ty;
+    let constructor = match field.ident {
+        Some(ref field_name) => quote!(|original| #input_type{#field_name: original}),
+        None => quote!(#input_type),
+    };
+    Ok(quote!{
+        impl #impl_generics ::core::str::FromStr for #input_type #ty_generics #where_clause {
+            type Err = <#field_ty as ::core::str::FromStr>::Err;
+            fn from_str(src: &str) -> ::core::result::Result<Self, Self::Err> {
+                <#field_ty as ::core::str::FromStr>::from_str(src).map(#constructor)
+            }
+        }
+    })
+}
+
+/// Matches the names of the variants of a fieldless enum and generates an error type like
+/// `ParseColorError` for strings that don't match any of them
+fn enum_from_str(input: &DeriveInput,
+                 attrs: &Attrs,
+                 data: &DataEnum,
+                 trait_name: &str)
+                 -> Result<TokenStream, Error> {
+    let input_type = &input.ident;
+    let case_insensitive = attrs.ty.flag("case_insensitive");
+    let rule = match attrs.ty.strs("rename_all").last() {
+        Some(rule) if !RENAME_RULES.contains(&rule.value().as_str()) => {
+            Err(Error::new(rule.span(),
+                           format!("unknown rename_all policy `{}`, expected one of: {}",
+                                   rule.value(),
+                                   RENAME_RULES.join(", "))))
+        }
+        rule => Ok(rule.map(LitStr::value)),
+    };
+
+    let variants = data.variants.iter().map(|variant| {
+        if variant.fields.is_empty() {
+            Ok(variant)
+        } else {
+            Err(Error::new_spanned(&variant.ident,
+                                   format!("derive({}) can only be used on enums without \
+                                            fields, but `{}` has fields",
+                                           trait_name,
+                                           variant.ident)))
+        }
+    });
+    let (rule, variants) = diagnostics::join(rule, diagnostics::collect(variants))?;
+
+    let mut accepted: Vec<String> = vec![];
+    let mut arms = vec![];
+    let mut errors = vec![];
+    for variant in variants {
+        let variant_ident = &variant.ident;
+        let name = match rule {
+            Some(ref rule) => rename(&variant_ident.to_string(), rule),
+            None => variant_ident.to_string(),
+        };
+        let mut names = vec![(name, variant_ident.span())];
+        for alias in attrs.variant(variant).strs("alias") {
+            names.push((alias.value(), alias.span()));
+        }
+        let mut conditions = vec![];
+        for (name, span) in names {
+            let duplicate = accepted.iter().any(|other| if case_insensitive {
+                other.eq_ignore_ascii_case(&name)
+            } else {
+                *other == name
+            });
+            if duplicate {
+                errors.push(Error::new(span,
+                                       format!("`{}` is accepted by more than one variant",
+                                               name)));
+                continue;
+            }
+            conditions.push(if case_insensitive {
+                quote!(src.eq_ignore_ascii_case(#name))
+            } else {
+                quote!(src == #name)
+            });
+            accepted.push(name);
+        }
+        arms.push(quote! {
+            if #(#conditions)||* {
+                return ::core::result::Result::Ok(#input_type::#variant_ident);
+            }
+        });
+    }
+    if let Some(error) = errors.into_iter().reduce(|mut error, other| {
+        error.combine(other);
+        error
+    }) {
+        return Err(error);
+    }
+
+    let vis = &input.vis;
+    let error_type = Ident::new(&format!("Parse{}Error", input_type), Span::call_site());
+    let error_doc = format!("The error returned when a string doesn't match any variant of \
+                             [`{}`].",
+                            input_type);
+    let message = match accepted.len() {
+        0 => format!("`{}` can't be parsed from a string", input_type),
+        _ => {
+            let quoted: Vec<_> = accepted.iter().map(|name| format!("`{}`", name)).collect();
+            format!("expected one of: {}", quoted.join(", "))
+        }
+    };
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        #[doc = #error_doc]
+        #[derive(::core::clone::Clone, ::core::marker::Copy, ::core::fmt::Debug,
+                 ::core::cmp::Eq, ::core::cmp::PartialEq)]
+        #vis struct #error_type;
+
+        impl #error_type {
+            /// Returns the strings that are accepted when parsing.
+            #vis fn accepted(&self) -> &'static [&'static str] {
+                &[#(#accepted),*]
+            }
+        }
+
+        impl ::core::fmt::Display for #error_type {
+            fn fmt(&self, __formatter: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                __formatter.write_str(#message)
+            }
+        }
+
+        impl ::core::error::Error for #error_type {}
+
+        impl #impl_generics ::core::str::FromStr for #input_type #ty_generics #where_clause {
+            type Err = #error_type;
+            fn from_str(src: &str) -> ::core::result::Result<Self, Self::Err> {
+                #(#arms)*
+                ::core::result::Result::Err(#error_type)
+            }
+        }
+    })
+}
+
+fn enum_only(attrs: &Attrs, key: &str, trait_name: &str) -> Error {
+    let message = format!("option `{}` of derive({}) can only be used on enums",
+                          key,
+                          trait_name);
+    match attrs.ty.strs(key).next() {
+        Some(value) => Error::new(value.span(), message),
+        None => Error::new(Span::call_site(), message),
+    }
+}
@@ -0,0 +1,77 @@
+use proc_macro2::{Span, TokenStream};
+use syn::{Data, DeriveInput, Ident};
+use crate::attrs::{Attrs, Position, Spec, DEFAULT_SPEC};
+use crate::diagnostics::{self, Error};
+use crate::utils::{add_extra_generic_param, add_extra_where_clauses, bounds, marked_field};
+
+/// `#[index]` picks the field to forward to. `IndexMut` understands the options of `Index` as
+/// well, so the field only has to be marked once.
+const SPEC: Spec = Spec {
+    type_keys: DEFAULT_SPEC.type_keys,
+    variant_keys: &[],
+    field_keys: &[],
+    markers: &[Position::Field],
+    shared: Some("index"),
+};
+
+/// Provides the hook to expand `#[derive(Index)]` and `#[derive(IndexMut)]` into an
+/// implementation of the trait that forwards to a field
+pub fn expand(input: &DeriveInput, trait_name: &str) -> Result<TokenStream, Error> {
+    let trait_ident = Ident::new(trait_name, Span::call_site());
+    let input_type = &input.ident;
+    let spec = match trait_name {
+        "Index" => Spec { shared: None, ..SPEC },
+        _ => SPEC,
+    };
+    let attrs = Attrs::parse(input, trait_name, &spec);
+    let fields = match input.data {
+        Data::Struct(ref data) => Ok(&data.fields),
+        Data::Enum(ref data) => {
+            Err(diagnostics::unsupported_enum(trait_name, data.enum_token, input_type))
+        }
+        Data::Union(ref data) => Err(diagnostics::unsupported_union(trait_name, data.union_token)),
+    };
+    let (attrs, fields) = diagnostics::join(attrs, fields)?;
+    let attrs = &attrs;
+    let field = marked_field(input, attrs, fields, trait_name, "index");
+    let (bounds, (member, field)) = diagnostics::join(bounds(&attrs.ty), field)?;
+    let field_ty = &field.ty;
+
+    // The type of the index gets its own type parameter, so every index the field supports can
+    // be used on the struct as well.
+    let index_ident = Ident::new("__IdxT", Span::call_site());
+    let new_generics = add_extra_generic_param(&input.generics, &index_ident);
+    let predicates = match bounds {
+        Some(predicates) => predicates,
+        None => vec![parse_quote!(#field_ty: ::core::ops::#trait_ident<#index_ident>)],
+    };
+    let new_generics = add_extra_where_clauses(&new_generics, predicates);
+    let (impl_generics, _, where_clause) = new_generics.split_for_impl();
+    let (_, ty_generics, _) = input.generics.split_for_impl();
+
+    let body = match trait_name {
+        "Index" => {
+            quote! {
+                type Output = <#field_ty as ::core::ops::Index<#index_ident>>::Output;
+                fn index(&self, index: #index_ident) -> &Self::Output {
+                    ::core::ops::Index::index(&self.#member, index)
+                }
+            }
+        }
+        _ => {
+            quote! {
+                fn index_mut(&mut self, index: #index_ident) -> &mut Self::Output {
+                    ::core::ops::IndexMut::index_mut(&mut self.#member, index)
+                }
+            }
+        }
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::core::ops::#trait_ident<#index_ident> for #input_type #ty_generics
+            #where_clause
+        {
+            #body
+        }
+    })
+}
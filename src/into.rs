@@ -0,0 +1,49 @@
+use proc_macro2::TokenStream;
+use syn::{Data, DeriveInput};
+use crate::attrs::{Attrs, DEFAULT_SPEC};
+use crate::diagnostics::{self, Error};
+use crate::utils::{add_extra_where_clauses, bounds, field_member};
+
+/// Provides the hook to expand `#[derive(Into)]` into an implementation of `From` for the
+/// type of the field, or a tuple of the types of all fields
+pub fn expand(input: &DeriveInput, trait_name: &str) -> Result<TokenStream, Error> {
+    let input_type = &input.ident;
+    let attrs = Attrs::parse(input, trait_name, &DEFAULT_SPEC);
+    let fields = match input.data {
+        Data::Struct(ref data) if data.fields.is_empty() => {
+            Err(diagnostics::unsupported_unit_struct(trait_name, input_type))
+        }
+        Data::Struct(ref data) => Ok(&data.fields),
+        Data::Enum(ref data) => {
+            Err(diagnostics::unsupported_enum(trait_name, data.enum_token, input_type))
+        }
+        Data::Union(ref data) => Err(diagnostics::unsupported_union(trait_name, data.union_token)),
+    };
+    let (attrs, fields) = diagnostics::join(attrs, fields)?;
+    let predicates = bounds(&attrs.ty)?.unwrap_or_default();
+    let generics = add_extra_where_clauses(&input.generics, predicates);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let types: Vec<_> = fields.iter().map(|f| &f.ty).collect();
+    let members: Vec<_> = fields.iter()
+        .enumerate()
+        .map(|(i, f)| field_member(i, f))
+        .collect();
+    let (into_type, value) = match members.len() {
+        1 => {
+            let member = &members[0];
+            (quote!(#(#types)*), quote!(original.#member))
+        }
+        _ => (quote!((#(#types),*)), quote!((#(original.#members),*))),
+    };
+
+    Ok(quote!{
+        impl #impl_generics ::core::convert::From<#input_type #ty_generics> for #into_type
+            #where_clause
+        {
+            fn from(original: #input_type #ty_generics) -> #into_type {
+                #value
+            }
+        }
+    })
+}
@@ -0,0 +1,41 @@
+use proc_macro2::{Span, TokenStream};
+use syn::{Data, DeriveInput, Ident};
+use crate::attrs::{Attrs, DEFAULT_SPEC};
+use crate::diagnostics::{self, Error};
+use crate::utils::rename;
+
+/// Provides the hook to expand `#[derive(IsVariant)]` into an `is_*` method for every variant
+pub fn expand(input: &DeriveInput, trait_name: &str) -> Result<TokenStream, Error> {
+    let input_type = &input.ident;
+    let attrs = Attrs::parse(input, trait_name, &DEFAULT_SPEC);
+    let variants = match input.data {
+        Data::Enum(ref data) => Ok(&data.variants),
+        Data::Struct(ref data) => {
+            Err(diagnostics::unsupported_struct(trait_name, data.struct_token, input_type))
+        }
+        Data::Union(ref data) => Err(diagnostics::unsupported_union(trait_name, data.union_token)),
+    };
+    let (_, variants) = diagnostics::join(attrs, variants)?;
+
+    let methods = variants.iter().map(|variant| {
+        let variant_ident = &variant.ident;
+        let method_name = format!("is_{}", rename(&variant_ident.to_string(), "snake_case"));
+        let method_ident = Ident::new(&method_name, Span::call_site());
+        let doc = format!("Returns `true` if this is a [`{0}::{1}`](enum.{0}.html#variant.{1}).",
+                          input_type,
+                          variant_ident);
+        quote! {
+            #[doc = #doc]
+            pub fn #method_ident(&self) -> bool {
+                ::core::matches!(self, #input_type::#variant_ident { .. })
+            }
+        }
+    });
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics #input_type #ty_generics #where_clause {
+            #(#methods)*
+        }
+    })
+}
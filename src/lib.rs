@@ -14,19 +14,48 @@
 //! By using this library the following code just works:
 //!
 //! ```rust
-//! #[derive(Eq, From, Add)]
+//! # #[macro_use] extern crate derive_more;
+//! #[derive(Debug, Eq, PartialEq, From, Add)]
 //! struct MyInt(i32);
 //!
-//! #[derive(Eq, Mul)]
-//! struct Point2D { x: i32, y: i32 };
+//! #[derive(Debug, Eq, PartialEq, From, Mul)]
+//! struct Point2D { x: i32, y: i32 }
 //!
 //! fn main() {
 //!     let my_11 = MyInt(5) + 6.into();
-//!     assert_eq!(MyInt(11), MyInt(5) + 6.into())
+//!     assert_eq!(MyInt(11), my_11);
 //!     assert_eq!(Point2D { x: 5, y: 6 } * 10, (50, 60).into());
 //! }
+//! ```
+//!
+//! All of these derives also work for generic types, such as `struct Point2D<T> { x: T, y: T }`.
+//! The required trait bounds are added to the generated implementations automatically.
+//! These bounds are put on the types of the fields that use a type parameter (e.g.
+//! `Vec<T>: Add<Output=Vec<T>>`), not on the type parameters themselves.
+//! That way a parameter that is only used in a marker field doesn't need to implement the trait.
+//! If the inferred bounds are wrong, they can be replaced with your own `where` predicates:
 //!
+//! ```rust
+//! # #[macro_use] extern crate derive_more;
+//! #[derive(Add, Mul)]
+//! #[derive_more(bound = "T: Copy + std::ops::Add<Output = T>")]
+//! #[derive_more(Mul(bound = "__RhsT: std::ops::Mul<T, Output = T>"))]
+//! struct Wrapper<T>(T);
+//! # fn main() {}
 //! ```
+//!
+//! `bound` applies to every derive of the type, while nesting it in the name of a trait only
+//! applies it to that derive.
+//! Every derive also has its own attribute, named after the trait in snake case, so
+//! `#[mul(bound = "...")]` and `#[bit_and(bound = "...")]` work as well.
+//! Options that a derive doesn't understand, or that are put on the wrong item, result in a
+//! compile error.
+//! The right hand side of the `Mul`-like derives is always called `__RhsT`.
+//! Lifetime parameters are carried over as well, so borrowing types like
+//! `struct Slice<'a>(&'a [u8])` can derive these traits just like owned ones.
+//!
+//! The generated code only uses paths from `::core`, so all the derives can be used in
+//! `#![no_std]` crates as well.
 
 //! ## The newly derivable traits
 //!
@@ -41,6 +70,18 @@
 //! 4. `AddAssign`-like, contains [`AddAssign`], [`SubAssign`], [`BitAndAssign`], [`BitOrAssign`]
 //!    and [`BitXorAssign`].
 //! 5. `Mul`-like, contains [`Mul`], [`Div`], [`Rem`], [`Shr`] and [`Shl`].
+//! 6. `Display`, only contains [`Display`].
+//! 7. `Error`, only contains [`Error`].
+//! 8. `FromStr`, only contains [`FromStr`].
+//! 9. `Into`, only contains [`Into`].
+//!    `TryInto` is its counterpart for enums.
+//! 10. `Deref`-like, contains [`Deref`] and [`DerefMut`].
+//! 11. `Index`-like, contains [`Index`] and [`IndexMut`].
+//! 12. `AsRef`-like, contains [`AsRef`] and [`AsMut`].
+//! 13. `Constructor`, which isn't a trait but generates a `new` function.
+//! 14. `IsVariant`, which isn't a trait but generates an `is_*` method for every variant.
+//! 15. `Unwrap`-like, which aren't traits either but generate `unwrap_*`, `as_*`, `as_*_mut` and
+//!     `try_unwrap_*` methods for every variant.
 //!
 //!
 //! ## Generated code
@@ -55,6 +96,16 @@
 //! 3. [`#[derive(Add)]`](add.html)
 //! 4. [`#[derive(AddAssign)]`](add_assign.html)
 //! 5. [`#[derive(Mul)]`](mul.html)
+//! 6. [`#[derive(Display)]`](display.html)
+//! 7. [`#[derive(Error)]`](error.html)
+//! 8. [`#[derive(FromStr)]`](from_str.html)
+//! 9. [`#[derive(Into)]`](into.html) and [`#[derive(TryInto)]`](try_into.html)
+//! 10. [`#[derive(Deref)]`](deref.html)
+//! 11. [`#[derive(Index)]`](index.html)
+//! 12. [`#[derive(AsRef)]`](as_ref.html)
+//! 13. [`#[derive(Constructor)]`](constructor.html)
+//! 14. [`#[derive(IsVariant)]`](is_variant.html)
+//! 15. [`#[derive(Unwrap)]`](unwrap.html)
 //!
 //! If you want to be sure what code is generated for your specific trait I recommend using the
 //! [`cargo-expand`] utility.
@@ -65,6 +116,8 @@
 //! This library heavily uses Macros 1.1, which is to stabilized in Rust 1.15 (the next Rust
 //! release).
 //! To use it before that time you have to install the nightly or beta channel.
+//! The `Error` implementations generated by `#[derive(Error)]`, `#[derive(FromStr)]` and
+//! `#[derive(TryUnwrap)]` use `core::error::Error`, which needs at least Rust 1.81.
 //!
 //! After doing this, add this to `Cargo.toml`:
 //!
@@ -99,14 +152,29 @@
 //! [`BitAndAssign`]: https://doc.rust-lang.org/std/ops/trait.BitAndAssign.html
 //! [`BitOrAssign`]: https://doc.rust-lang.org/std/ops/trait.BitOrAssign.html
 //! [`BitXorAssign`]: https://doc.rust-lang.org/std/ops/trait.BitXorAssign.html
+//! [`Display`]: https://doc.rust-lang.org/std/fmt/trait.Display.html
+//! [`Error`]: https://doc.rust-lang.org/std/error/trait.Error.html
+//! [`FromStr`]: https://doc.rust-lang.org/std/str/trait.FromStr.html
+//! [`Into`]: https://doc.rust-lang.org/std/convert/trait.Into.html
+//! [`Deref`]: https://doc.rust-lang.org/std/ops/trait.Deref.html
+//! [`DerefMut`]: https://doc.rust-lang.org/std/ops/trait.DerefMut.html
+//! [`Index`]: https://doc.rust-lang.org/std/ops/trait.Index.html
+//! [`IndexMut`]: https://doc.rust-lang.org/std/ops/trait.IndexMut.html
+//! [`AsRef`]: https://doc.rust-lang.org/std/convert/trait.AsRef.html
+//! [`AsMut`]: https://doc.rust-lang.org/std/convert/trait.AsMut.html
 
 extern crate proc_macro;
+extern crate proc_macro2;
 #[macro_use]
 extern crate quote;
+#[macro_use]
 extern crate syn;
 
 use proc_macro::TokenStream;
+use syn::DeriveInput;
 
+mod attrs;
+mod diagnostics;
 mod utils;
 
 mod from;
@@ -114,38 +182,78 @@ mod add_like;
 mod add_assign_like;
 mod mul_like;
 mod not_like;
+mod display;
+mod error;
+mod from_str;
+mod into;
+mod try_into;
+mod deref;
+mod index;
+mod as_ref;
+mod constructor;
+mod is_variant;
+mod unwrap;
 
 macro_rules! create_derive(
-    ($mod_:ident, $trait_:ident, $fn_name: ident) => {
-        #[proc_macro_derive($trait_)]
+    ($mod_:ident, $trait_:ident, $fn_name: ident, $($attr:ident),+) => {
+        #[proc_macro_derive($trait_, attributes(derive_more, $($attr),+))]
         #[doc(hidden)]
         pub fn $fn_name(input: TokenStream) -> TokenStream {
-            let s = input.to_string();
-            let ast = syn::parse_macro_input(&s).unwrap();
-            $mod_::expand(&ast, stringify!($trait_)).parse().unwrap()
+            let input = parse_macro_input!(input as DeriveInput);
+            $mod_::expand(&input, stringify!($trait_))
+                .unwrap_or_else(|error| error.to_compile_error())
+                .into()
         }
     }
 );
 
-create_derive!(from, From, from_derive);
+create_derive!(from, From, from_derive, from);
+
+create_derive!(add_like, Add, add_derive, add);
+create_derive!(add_like, Sub, sub_derive, sub);
+create_derive!(add_like, BitAnd, bit_and_derive, bit_and);
+create_derive!(add_like, BitOr, bit_or_derive, bit_or);
+create_derive!(add_like, BitXor, bit_xor_derive, bit_xor);
+
+create_derive!(mul_like, Mul, mul_derive, mul);
+create_derive!(mul_like, Div, div_derive, div);
+create_derive!(mul_like, Rem, rem_derive, rem);
+create_derive!(mul_like, Shr, shr_derive, shr);
+create_derive!(mul_like, Shl, shl_derive, shl);
+
+create_derive!(not_like, Not, not_derive, not);
+create_derive!(not_like, Neg, neg_derive, neg);
+
+create_derive!(add_assign_like, AddAssign,    add_assign_derive,     add_assign);
+create_derive!(add_assign_like, SubAssign,    sub_assign_derive,     sub_assign);
+create_derive!(add_assign_like, BitAndAssign, bit_and_assign_derive, bit_and_assign);
+create_derive!(add_assign_like, BitOrAssign,  bit_or_assign_derive,  bit_or_assign);
+create_derive!(add_assign_like, BitXorAssign, bit_xor_assign_derive, bit_xor_assign);
+
+create_derive!(display, Display, display_derive, display);
+create_derive!(error, Error, error_derive, error);
+create_derive!(from_str, FromStr, from_str_derive, from_str);
+create_derive!(into, Into, into_derive, into);
+create_derive!(try_into, TryInto, try_into_derive, try_into);
+
+create_derive!(deref, Deref, deref_derive, deref);
+create_derive!(deref, DerefMut, deref_mut_derive, deref_mut, deref);
 
-create_derive!(add_like, Add, add_derive);
-create_derive!(add_like, Sub, sub_derive);
-create_derive!(add_like, BitAnd, bit_and_derive);
-create_derive!(add_like, BitOr, bit_or_derive);
-create_derive!(add_like, BitXor, bit_xor_derive);
+create_derive!(index, Index, index_derive, index);
+create_derive!(index, IndexMut, index_mut_derive, index_mut, index);
 
-create_derive!(mul_like, Mul, mul_derive);
-create_derive!(mul_like, Div, div_derive);
-create_derive!(mul_like, Rem, rem_derive);
-create_derive!(mul_like, Shr, shr_derive);
-create_derive!(mul_like, Shl, shl_derive);
+create_derive!(as_ref, AsRef, as_ref_derive, as_ref);
+create_derive!(as_ref, AsMut, as_mut_derive, as_mut, as_ref);
 
-create_derive!(not_like, Not, not_derive);
-create_derive!(not_like, Neg, neg_derive);
+create_derive!(constructor, Constructor, constructor_derive, constructor);
+create_derive!(is_variant, IsVariant, is_variant_derive, is_variant);
+create_derive!(unwrap, Unwrap, unwrap_derive, unwrap);
+create_derive!(unwrap, TryUnwrap, try_unwrap_derive, try_unwrap);
 
-create_derive!(add_assign_like, AddAssign,    add_assign_derive);
-create_derive!(add_assign_like, SubAssign,    sub_assign_derive);
-create_derive!(add_assign_like, BitAndAssign, bit_and_assign_derive);
-create_derive!(add_assign_like, BitOrAssign,  bit_or_assign_derive);
-create_derive!(add_assign_like, BitXorAssign, bit_xor_assign_derive);
+/// The traits derived above, which can group options in `#[derive_more(...)]`
+const TRAIT_NAMES: &[&str] = &["From", "Add", "Sub", "BitAnd", "BitOr", "BitXor", "Mul", "Div",
+                               "Rem", "Shr", "Shl", "Not", "Neg", "AddAssign", "SubAssign",
+                               "BitAndAssign", "BitOrAssign", "BitXorAssign", "Display", "Error",
+                               "FromStr", "Into", "TryInto", "Deref", "DerefMut", "Index",
+                               "IndexMut", "AsRef", "AsMut", "Constructor", "IsVariant", "Unwrap",
+                               "TryUnwrap"];
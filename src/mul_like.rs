@@ -1,71 +1,132 @@
-use quote::{Tokens, ToTokens};
-use syn::{Body, Field, Ident, VariantData, MacroInput, Ty};
-use std::iter;
+use proc_macro2::{Span, TokenStream};
+use quote::ToTokens;
+use syn::{Data, DataStruct, DeriveInput, Field, Fields, Ident, Type};
+use syn::punctuated::Punctuated;
+use syn::token::Comma;
 use std::collections::HashSet;
+use crate::attrs::{Attrs, Kind, Spec, DEFAULT_SPEC};
+use crate::diagnostics::{self, Error};
+use crate::utils::{add_extra_generic_param, add_extra_where_clauses, bounds, field_member,
+                   is_phantom_data};
 
+/// `skip` keeps the value of a field as it is and `with` combines a field with the right hand
+/// side using a function instead of the trait method
+const SPEC: Spec = Spec {
+    type_keys: DEFAULT_SPEC.type_keys,
+    variant_keys: &[],
+    field_keys: &[("skip", Kind::Flag), ("with", Kind::Path)],
+    markers: &[],
+    shared: None,
+};
 
-pub fn expand(input: &MacroInput, trait_name: &str) -> Tokens {
-    let trait_ident = Ident::from(trait_name);
-    let trait_path = quote!(::std::ops::#trait_ident);
+pub fn expand(input: &DeriveInput, trait_name: &str) -> Result<TokenStream, Error> {
+    let trait_ident = Ident::new(trait_name, Span::call_site());
+    let trait_path = quote!(::core::ops::#trait_ident);
     let method_name = trait_name.to_lowercase();
-    let method_ident = &Ident::from(method_name);
+    let method_ident = &Ident::new(&method_name, Span::call_site());
     let input_type = &input.ident;
-
-    let ((block, tys), num_fields) = match input.body {
-        Body::Struct(VariantData::Tuple(ref fields)) => {
-            (tuple_content(input_type, fields, method_ident), fields.len())
+    let attrs = Attrs::parse(input, trait_name, &SPEC);
+    let shape = match input.data {
+        Data::Struct(DataStruct { fields: Fields::Unit, .. }) => {
+            Err(diagnostics::unsupported_unit_struct(trait_name, input_type))
         }
-        Body::Struct(VariantData::Struct(ref fields)) => {
-            (struct_content(input_type, fields, method_ident), fields.len())
+        Data::Struct(_) => Ok(()),
+        Data::Enum(ref data) => {
+            Err(diagnostics::unsupported_enum(trait_name, data.enum_token, input_type))
         }
+        Data::Union(ref data) => Err(diagnostics::unsupported_union(trait_name, data.union_token)),
+    };
+    let (attrs, ()) = diagnostics::join(attrs, shape)?;
+    let attrs = &attrs;
 
-        _ => panic!(format!("Only structs can use derive({})", trait_name)),
+    let (block, tys, num_fields) = match input.data {
+        Data::Struct(DataStruct { fields: Fields::Unnamed(ref fields), .. }) => {
+            tuple_content(input_type, &fields.unnamed, attrs, method_ident)
+        }
+        Data::Struct(DataStruct { fields: Fields::Named(ref fields), .. }) => {
+            struct_content(input_type, &fields.named, attrs, method_ident)
+        }
+        _ => unreachable!(),
     };
+    let bounds = bounds(&attrs.ty)?;
     let mut constraints: Vec<_> = tys.iter().map(|t| quote!(#trait_path<#t, Output=#t>)).collect();
 
     if num_fields > 1 {
         // If the struct has more than one field the rhs needs to be copied for each
         // field
-        constraints.push(quote!(::std::marker::Copy))
+        constraints.push(quote!(::core::marker::Copy))
     }
-    quote!(
-        impl <T> #trait_path<T> for #input_type where T:
-                #(#constraints)+* {
-            type Output = #input_type;
-            fn #method_ident(self, rhs: T) -> #input_type {
+
+    // The type of the right hand side gets its own type parameter, so it doesn't clash with
+    // the type parameters of the input type itself.
+    let rhs_ident = Ident::new("__RhsT", Span::call_site());
+    let new_generics = add_extra_generic_param(&input.generics, &rhs_ident);
+    let predicates = match bounds {
+        Some(predicates) => predicates,
+        None => vec![parse_quote!(#rhs_ident: #(#constraints)+*)],
+    };
+    let new_generics = add_extra_where_clauses(&new_generics, predicates);
+    let (impl_generics, _, where_clause) = new_generics.split_for_impl();
+    let (_, ty_generics, _) = input.generics.split_for_impl();
+
+    Ok(quote!(
+        impl #impl_generics #trait_path<#rhs_ident> for #input_type #ty_generics #where_clause {
+            type Output = #input_type #ty_generics;
+            fn #method_ident(self, rhs: #rhs_ident) -> #input_type #ty_generics {
                 #block
             }
         }
 
-    )
+    ))
 }
 
 fn tuple_content<'a, T: ToTokens>(input_type: &T,
-                                  fields: &'a Vec<Field>,
+                                  fields: &'a Punctuated<Field, Comma>,
+                                  attrs: &Attrs,
                                   method_ident: &Ident)
-                                  -> (Tokens, HashSet<&'a Ty>) {
-    let tys: HashSet<_> = fields.iter().map(|f| &f.ty).collect();
-    let count = (0..fields.len()).map(|i| Ident::from(i.to_string()));
-    let method_iter = iter::repeat(method_ident);
-
-    let body = quote!(#input_type(#(rhs.#method_iter(self.#count)),*));
-    (body, tys)
+                                  -> (TokenStream, HashSet<&'a Type>, usize) {
+    let (exprs, tys, num_fields) = field_exprs(fields, attrs, method_ident);
+    let body = quote!(#input_type(#(#exprs),*));
+    (body, tys, num_fields)
 }
 
 fn struct_content<'a, T: ToTokens>(input_type: &T,
-                                   fields: &'a Vec<Field>,
+                                   fields: &'a Punctuated<Field, Comma>,
+                                   attrs: &Attrs,
                                    method_ident: &Ident)
-                                   -> (Tokens, HashSet<&'a Ty>) {
-    let tys: HashSet<_> = fields.iter().map(|f| &f.ty).collect();
-    let field_names: &Vec<_> = &fields.iter()
-        .map(|f| f.ident.as_ref().unwrap())
-        .collect();
-    // This is to fix an error in quote when using the same name twice
-    let field_names2 = field_names;
-    let method_iter = iter::repeat(method_ident);
+                                   -> (TokenStream, HashSet<&'a Type>, usize) {
+    let (exprs, tys, num_fields) = field_exprs(fields, attrs, method_ident);
+    // It's safe to unwrap because struct fields always have an identifier
+    let field_names = fields.iter().map(|f| f.ident.as_ref().unwrap());
+    let body = quote!(#input_type{#(#field_names: #exprs),*});
+    (body, tys, num_fields)
+}
 
-    let body = quote!{
-        #input_type{#(#field_names: rhs.#method_iter(self.#field_names2)),*}
-    };
-    (body, tys)
+/// Generates `rhs.mul(self.x)` for every field.
+/// Also returns the types of the fields that are combined with the trait method, and how many
+/// fields use the right hand side.
+fn field_exprs<'a>(fields: &'a Punctuated<Field, Comma>,
+                   attrs: &Attrs,
+                   method_ident: &Ident)
+                   -> (Vec<TokenStream>, HashSet<&'a Type>, usize) {
+    let mut exprs = vec![];
+    let mut tys = HashSet::new();
+    let mut num_fields = 0;
+    for (i, field) in fields.iter().enumerate() {
+        let member = field_member(i, field);
+        let options = attrs.field(field);
+        if is_phantom_data(&field.ty) {
+            exprs.push(quote!(::core::marker::PhantomData));
+        } else if options.flag("skip") {
+            exprs.push(quote!(self.#member));
+        } else if let Some(with) = options.path("with") {
+            num_fields += 1;
+            exprs.push(quote!(#with(self.#member, rhs)));
+        } else {
+            tys.insert(&field.ty);
+            num_fields += 1;
+            exprs.push(quote!(rhs.#method_ident(self.#member)));
+        }
+    }
+    (exprs, tys, num_fields)
 }
@@ -1,44 +1,96 @@
-use quote::{Tokens, ToTokens};
-use syn::{Body, Field, Ident, Variant, VariantData, MacroInput};
-use std::iter;
-
-pub fn expand(input: &MacroInput, trait_name: &str) -> Tokens {
-    let trait_ident = Ident::from(trait_name);
+use proc_macro2::{Span, TokenStream};
+use quote::ToTokens;
+use syn::{Data, DataStruct, DeriveInput, Field, Fields, Ident, Index, TypeGenerics, Variant};
+use syn::punctuated::Punctuated;
+use syn::token::Comma;
+use crate::attrs::{Attrs, Kind, Spec, DEFAULT_SPEC};
+use crate::diagnostics::{self, Error};
+use crate::utils::{add_field_bounds, is_phantom_data, numbered_vars};
+
+/// `skip` keeps the value of a field as it is
+const SPEC: Spec = Spec {
+    type_keys: DEFAULT_SPEC.type_keys,
+    variant_keys: &[],
+    field_keys: &[("skip", Kind::Flag)],
+    markers: &[],
+    shared: None,
+};
+
+pub fn expand(input: &DeriveInput, trait_name: &str) -> Result<TokenStream, Error> {
+    let trait_ident = Ident::new(trait_name, Span::call_site());
     let method_name = trait_name.to_lowercase();
-    let method_ident = &Ident::from(method_name);
+    let method_ident = &Ident::new(&method_name, Span::call_site());
     let input_type = &input.ident;
 
-    let (output_type, block) = match input.body {
-        Body::Struct(VariantData::Tuple(ref fields)) => {
-            (quote!(#input_type), tuple_content(input_type, fields, method_ident))
+    let attrs = Attrs::parse(input, trait_name, &SPEC);
+    let shape = match input.data {
+        Data::Struct(DataStruct { fields: Fields::Unit, .. }) => {
+            Err(diagnostics::unsupported_unit_struct(trait_name, input_type))
         }
-        Body::Struct(VariantData::Struct(ref fields)) => {
-            (quote!(#input_type), struct_content(input_type, fields, method_ident))
+        Data::Union(ref data) => Err(diagnostics::unsupported_union(trait_name, data.union_token)),
+        _ => Ok(()),
+    };
+    let (attrs, ()) = diagnostics::join(attrs, shape)?;
+    let attrs = &attrs;
+    let generics = add_field_bounds(input,
+                                    attrs,
+                                    |ty| quote!(::core::ops::#trait_ident<Output=#ty>))?;
+    let (_, ty_generics, _) = input.generics.split_for_impl();
+
+    let (output_type, block) = match input.data {
+        Data::Struct(ref data) => {
+            match data.fields {
+                Fields::Unnamed(ref fields) => {
+                    (quote!(#input_type #ty_generics),
+                     tuple_content(input_type, &fields.unnamed, attrs, method_ident))
+                }
+                Fields::Named(ref fields) => {
+                    (quote!(#input_type #ty_generics),
+                     struct_content(input_type, &fields.named, attrs, method_ident))
+                }
+                Fields::Unit => unreachable!(),
+            }
         }
-        Body::Enum(ref definition) => {
-            enum_output_type_and_content(input_type, definition, &method_ident)
+        Data::Enum(ref data) => {
+            enum_output_type_and_content(input_type,
+                                         &ty_generics,
+                                         &data.variants,
+                                         attrs,
+                                         method_ident)
         }
-
-        _ => panic!(format!("Only structs and enums can use dervie({})", trait_name)),
+        Data::Union(_) => unreachable!(),
     };
 
-    quote!(
-        impl ::std::ops::#trait_ident for #input_type {
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    Ok(quote!(
+        impl #impl_generics ::core::ops::#trait_ident for #input_type #ty_generics #where_clause {
             type Output = #output_type;
             fn #method_ident(self) -> #output_type {
                 #block
             }
         }
-    )
+    ))
 }
 
-fn tuple_content<T: ToTokens>(input_type: &T, fields: &Vec<Field>, method_ident: &Ident) -> Tokens {
+fn tuple_content<T: ToTokens>(input_type: &T,
+                              fields: &Punctuated<Field, Comma>,
+                              attrs: &Attrs,
+                              method_ident: &Ident)
+                              -> TokenStream {
     let mut exprs = vec![];
 
-    for i in 0..fields.len() {
-        let i = Ident::from(i.to_string());
-        // generates `self.0.add()`
-        let expr = quote!(self.#i.#method_ident());
+    for (i, field) in fields.iter().enumerate() {
+        let i = Index::from(i);
+        let expr = if is_phantom_data(&field.ty) {
+            quote!(::core::marker::PhantomData)
+        } else if attrs.field(field).flag("skip") {
+            // generates `self.0`
+            quote!(self.#i)
+        } else {
+            // generates `self.0.not()`
+            quote!(self.#i.#method_ident())
+        };
         exprs.push(expr);
     }
 
@@ -46,14 +98,24 @@ fn tuple_content<T: ToTokens>(input_type: &T, fields: &Vec<Field>, method_ident:
 }
 
 
-fn struct_content(input_type: &Ident, fields: &Vec<Field>, method_ident: &Ident) -> Tokens {
+fn struct_content(input_type: &Ident,
+                  fields: &Punctuated<Field, Comma>,
+                  attrs: &Attrs,
+                  method_ident: &Ident)
+                  -> TokenStream {
     let mut exprs = vec![];
 
     for field in fields {
-        // It's safe to unwrap because struct fields always have an identifier
         let field_id = field.ident.as_ref();
-        // generates `x: self.x.not()`
-        let expr = quote!(#field_id: self.#field_id.#method_ident());
+        let expr = if is_phantom_data(&field.ty) {
+            quote!(#field_id: ::core::marker::PhantomData)
+        } else if attrs.field(field).flag("skip") {
+            // generates `x: self.x`
+            quote!(#field_id: self.#field_id)
+        } else {
+            // generates `x: self.x.not()`
+            quote!(#field_id: self.#field_id.#method_ident())
+        };
         exprs.push(expr)
     }
 
@@ -61,28 +123,29 @@ fn struct_content(input_type: &Ident, fields: &Vec<Field>, method_ident: &Ident)
 }
 
 fn enum_output_type_and_content(input_type: &Ident,
-                                variants: &Vec<Variant>,
+                                ty_generics: &TypeGenerics,
+                                variants: &Punctuated<Variant, Comma>,
+                                attrs: &Attrs,
                                 method_ident: &Ident)
-                                -> (Tokens, Tokens) {
+                                -> (TokenStream, TokenStream) {
     let mut matches = vec![];
-    let mut method_iter = iter::repeat(method_ident);
     // If the enum contains unit types that means it can error.
-    let has_unit_type = variants.iter().any(|v| v.data == VariantData::Unit);
+    let has_unit_type = variants.iter().any(|v| v.fields == Fields::Unit);
 
     for variant in variants {
         let subtype = &variant.ident;
         let subtype = quote!(#input_type::#subtype);
 
-        match variant.data {
-            VariantData::Tuple(ref fields) => {
+        match variant.fields {
+            Fields::Unnamed(ref fields) => {
                 // The patern that is outputted should look like this:
                 // (Subtype(vars)) => Ok(TypePath(exprs))
-                let size = fields.len();
-                let vars: &Vec<_> = &(0..size).map(|i| Ident::from(format!("__{}", i))).collect();
-                let method_iter = method_iter.by_ref();
-                let mut body = quote!(#subtype(#(#vars.#method_iter()),*));
+                let size = fields.unnamed.len();
+                let vars = &numbered_vars(size, "");
+                let exprs = var_exprs(&fields.unnamed, attrs, vars, method_ident);
+                let mut body = quote!(#subtype(#(#exprs),*));
                 if has_unit_type {
-                    body = quote!(Ok(#body))
+                    body = quote!(::core::result::Result::Ok(#body))
                 }
                 let matcher = quote!{
                     #subtype(#(#vars),*) => {
@@ -91,19 +154,19 @@ fn enum_output_type_and_content(input_type: &Ident,
                 };
                 matches.push(matcher);
             }
-            VariantData::Struct(ref fields) => {
+            Fields::Named(ref fields) => {
                 // The patern that is outputted should look like this:
                 // (Subtype{a: __l_a, ...} => {
                 //     Ok(Subtype{a: __l_a.neg(__r_a), ...})
                 // }
-                let size = fields.len();
+                let size = fields.named.len();
                 let field_names: &Vec<_> =
-                    &fields.iter().map(|f| f.ident.as_ref().unwrap()).collect();
-                let vars: &Vec<_> = &(0..size).map(|i| Ident::from(format!("__{}", i))).collect();
-                let method_iter = method_iter.by_ref();
-                let mut body = quote!(#subtype{#(#field_names: #vars.#method_iter()),*});
+                    &fields.named.iter().map(|f| f.ident.as_ref().unwrap()).collect();
+                let vars = &numbered_vars(size, "");
+                let exprs = var_exprs(&fields.named, attrs, vars, method_ident);
+                let mut body = quote!(#subtype{#(#field_names: #exprs),*});
                 if has_unit_type {
-                    body = quote!(Ok(#body))
+                    body = quote!(::core::result::Result::Ok(#body))
                 }
                 let matcher = quote!{
                     #subtype{#(#field_names: #vars),*} => {
@@ -112,9 +175,9 @@ fn enum_output_type_and_content(input_type: &Ident,
                 };
                 matches.push(matcher);
             }
-            VariantData::Unit => {
-                let message = format!("Cannot {}() unit variants", method_ident.to_string());
-                matches.push(quote!(#subtype => Err(#message)));
+            Fields::Unit => {
+                let message = format!("Cannot {}() unit variants", method_ident);
+                matches.push(quote!(#subtype => ::core::result::Result::Err(#message)));
             }
         }
     }
@@ -126,10 +189,28 @@ fn enum_output_type_and_content(input_type: &Ident,
     );
 
     let output_type = if has_unit_type {
-        quote!(Result<#input_type, &'static str>)
+        quote!(::core::result::Result<#input_type #ty_generics, &'static str>)
     } else {
-        quote!(#input_type)
+        quote!(#input_type #ty_generics)
     };
 
     (output_type, body)
 }
+
+/// Generates `__0.not()` for every field of a variant, or just `__0` if it's skipped
+fn var_exprs(fields: &Punctuated<Field, Comma>,
+             attrs: &Attrs,
+             vars: &[Ident],
+             method_ident: &Ident)
+             -> Vec<TokenStream> {
+    fields.iter()
+        .zip(vars)
+        .map(|(field, var)| if is_phantom_data(&field.ty) {
+            quote!(::core::marker::PhantomData)
+        } else if attrs.field(field).flag("skip") {
+            quote!(#var)
+        } else {
+            quote!(#var.#method_ident())
+        })
+        .collect()
+}
@@ -0,0 +1,92 @@
+use proc_macro2::TokenStream;
+use syn::{Data, DeriveInput, Type};
+use crate::attrs::{Attrs, Kind, Spec, DEFAULT_SPEC};
+use crate::diagnostics::{self, Error};
+use crate::utils::{add_extra_where_clauses, bounds, numbered_vars, payload, payload_types,
+                   variant_pattern};
+
+/// `ignore` leaves a variant out, to settle which variant converts into a type that several of
+/// them hold
+const SPEC: Spec = Spec {
+    type_keys: DEFAULT_SPEC.type_keys,
+    variant_keys: &[("ignore", Kind::Flag)],
+    field_keys: &[],
+    markers: &[],
+    shared: None,
+};
+
+/// Provides the hook to expand `#[derive(TryInto)]` into an implementation of `TryFrom` for the
+/// fields of every variant, which gives the enum back as the error
+pub fn expand(input: &DeriveInput, trait_name: &str) -> Result<TokenStream, Error> {
+    let enum_ident = &input.ident;
+    let attrs = Attrs::parse(input, trait_name, &SPEC);
+    let variants = match input.data {
+        Data::Enum(ref data) => Ok(&data.variants),
+        Data::Struct(ref data) => {
+            Err(diagnostics::unsupported_struct(trait_name, data.struct_token, enum_ident))
+        }
+        Data::Union(ref data) => Err(diagnostics::unsupported_union(trait_name, data.union_token)),
+    };
+    let (attrs, variants) = diagnostics::join(attrs, variants)?;
+    let predicates = bounds(&attrs.ty)?.unwrap_or_default();
+    let generics = add_extra_where_clauses(&input.generics, predicates);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    // Variants are grouped by the type they convert into, so a variant with multiple fields and
+    // one with a tuple of the same types end up in the same group
+    let mut groups: Vec<(Type, Vec<_>)> = vec![];
+    for variant in variants {
+        if variant.fields.is_empty() || attrs.variant(variant).flag("ignore") {
+            continue;
+        }
+        let types: Vec<_> = variant.fields.iter().map(|f| &f.ty).collect();
+        let payload_ty = payload_types(&types).0;
+        let key: Type = parse_quote!(#payload_ty);
+        match groups.iter_mut().find(|group| group.0 == key) {
+            Some(group) => group.1.push((variant, types)),
+            None => groups.push((key, vec![(variant, types)])),
+        }
+    }
+
+    let mut tokens = TokenStream::new();
+    for (_, group) in groups {
+        let (variant, types) = &group[0];
+        let (payload_ty, _, _) = payload_types(types);
+        if group.len() > 1 {
+            // If more than one variant has these fields don't add automatic TryFrom, since it
+            // would be ambiguous, but tell the user how to pick one.
+            let names: Vec<_> = group.iter().map(|(variant, _)| &variant.ident).collect();
+            let message = format!("no `TryFrom<{}>` is generated for `{}`, because {} convert \
+                                   into it; mark all but one of them with #[try_into(ignore)]",
+                                  enum_ident,
+                                  payload_ty,
+                                  diagnostics::all_of(&names));
+            tokens.extend(diagnostics::warning(variant.ident.span(), &message));
+            continue;
+        }
+        let vars = numbered_vars(types.len(), "");
+        let pattern = variant_pattern(enum_ident, variant, &vars);
+        let value = payload(&vars);
+        let other = if variants.len() > 1 {
+            quote!(__other => ::core::result::Result::Err(__other),)
+        } else {
+            quote!()
+        };
+        tokens.extend(quote! {
+            impl #impl_generics ::core::convert::TryFrom<#enum_ident #ty_generics> for #payload_ty
+                #where_clause
+            {
+                type Error = #enum_ident #ty_generics;
+                fn try_from(original: #enum_ident #ty_generics)
+                    -> ::core::result::Result<Self, Self::Error>
+                {
+                    match original {
+                        #pattern => ::core::result::Result::Ok(#value),
+                        #other
+                    }
+                }
+            }
+        });
+    }
+    Ok(tokens)
+}
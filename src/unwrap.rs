@@ -0,0 +1,152 @@
+use proc_macro2::{Span, TokenStream};
+use syn::{Data, DeriveInput, Ident};
+use crate::attrs::{Attrs, DEFAULT_SPEC};
+use crate::diagnostics::{self, Error};
+use crate::utils::{numbered_vars, payload, payload_types, rename, variant_pattern};
+
+/// Provides the hook to expand `#[derive(Unwrap)]` into `unwrap_*`, `as_*` and `as_*_mut`
+/// methods, and `#[derive(TryUnwrap)]` into `try_unwrap_*` methods and their error type
+pub fn expand(input: &DeriveInput, trait_name: &str) -> Result<TokenStream, Error> {
+    let input_type = &input.ident;
+    let attrs = Attrs::parse(input, trait_name, &DEFAULT_SPEC);
+    let variants = match input.data {
+        Data::Enum(ref data) => Ok(&data.variants),
+        Data::Struct(ref data) => {
+            Err(diagnostics::unsupported_struct(trait_name, data.struct_token, input_type))
+        }
+        Data::Union(ref data) => Err(diagnostics::unsupported_union(trait_name, data.union_token)),
+    };
+    let (_, variants) = diagnostics::join(attrs, variants)?;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let error_type = Ident::new(&format!("TryUnwrap{}Error", input_type), Span::call_site());
+
+    // The name of the variant a value actually is, for the panic message and the error
+    let names = variants.iter().map(|variant| {
+        let variant_ident = &variant.ident;
+        let name = format!("{}::{}", input_type, variant_ident);
+        quote!(#input_type::#variant_ident { .. } => #name)
+    });
+    let found = quote!(match __other { #(#names),* });
+
+    let mut methods = vec![];
+    for variant in variants {
+        let variant_ident = &variant.ident;
+        let snake_name = rename(&variant_ident.to_string(), "snake_case");
+        let vars = numbered_vars(variant.fields.len(), "");
+        let pattern = variant_pattern(input_type, variant, &vars);
+        let types: Vec<_> = variant.fields.iter().map(|f| &f.ty).collect();
+        let expected = format!("{}::{}", input_type, variant_ident);
+
+        if trait_name == "Unwrap" {
+            let unwrap_name = format!("unwrap_{}", snake_name);
+            let unwrap_ident = Ident::new(&unwrap_name, Span::call_site());
+            let as_ident = Ident::new(&format!("as_{}", snake_name), Span::call_site());
+            let as_mut_ident = Ident::new(&format!("as_{}_mut", snake_name), Span::call_site());
+            let message = format!("called `{}::{}()` on a `{{}}` value", input_type, unwrap_name);
+            let (ty, ref_ty, mut_ty) = payload_types(&types);
+            let value = payload(&vars);
+            methods.push(quote! {
+                #[doc = concat!("Returns the fields of a `", #expected, "`.\n\n",
+                                "# Panics\n\nPanics if this is another variant.")]
+                #[track_caller]
+                pub fn #unwrap_ident(self) -> #ty {
+                    match self {
+                        #pattern => #value,
+                        __other => ::core::panic!(#message, #found),
+                    }
+                }
+
+                #[doc = concat!("Returns references to the fields of a `", #expected,
+                                "`, or `None` for another variant.")]
+                pub fn #as_ident(&self) -> ::core::option::Option<#ref_ty> {
+                    match self {
+                        #pattern => ::core::option::Option::Some(#value),
+                        _ => ::core::option::Option::None,
+                    }
+                }
+
+                #[doc = concat!("Returns mutable references to the fields of a `", #expected,
+                                "`, or `None` for another variant.")]
+                pub fn #as_mut_ident(&mut self) -> ::core::option::Option<#mut_ty> {
+                    match self {
+                        #pattern => ::core::option::Option::Some(#value),
+                        _ => ::core::option::Option::None,
+                    }
+                }
+            });
+        } else {
+            let try_unwrap_ident = Ident::new(&format!("try_unwrap_{}", snake_name),
+                                              Span::call_site());
+            let (ty, _, _) = payload_types(&types);
+            let value = payload(&vars);
+            methods.push(quote! {
+                #[doc = concat!("Returns the fields of a `", #expected, "`, or the value \
+                                 itself in the error for another variant.")]
+                pub fn #try_unwrap_ident(self)
+                    -> ::core::result::Result<#ty, #error_type #ty_generics>
+                {
+                    match self {
+                        #pattern => ::core::result::Result::Ok(#value),
+                        __other => {
+                            let found = #found;
+                            ::core::result::Result::Err(#error_type {
+                                input: __other,
+                                expected: #expected,
+                                found,
+                            })
+                        }
+                    }
+                }
+            });
+        }
+    }
+
+    let methods = quote! {
+        impl #impl_generics #input_type #ty_generics #where_clause {
+            #(#methods)*
+        }
+    };
+    if trait_name == "Unwrap" {
+        return Ok(methods);
+    }
+
+    let vis = &input.vis;
+    let generics = &input.generics;
+    let error_doc = format!("The error returned when a `try_unwrap_*` method is called on \
+                             another variant of [`{}`].",
+                            input_type);
+    let error_name = error_type.to_string();
+    Ok(quote! {
+        #methods
+
+        #[doc = #error_doc]
+        #vis struct #error_type #generics #where_clause {
+            /// The original value, so it isn't lost
+            pub input: #input_type #ty_generics,
+            /// The variant that was expected, like `Enum::Variant`
+            pub expected: &'static str,
+            /// The variant the value actually is
+            pub found: &'static str,
+        }
+
+        impl #impl_generics ::core::fmt::Debug for #error_type #ty_generics #where_clause {
+            fn fmt(&self, __formatter: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                __formatter.debug_struct(#error_name)
+                    .field("expected", &self.expected)
+                    .field("found", &self.found)
+                    .finish_non_exhaustive()
+            }
+        }
+
+        impl #impl_generics ::core::fmt::Display for #error_type #ty_generics #where_clause {
+            fn fmt(&self, __formatter: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                ::core::write!(__formatter,
+                               "expected a `{}` value, found a `{}` value",
+                               self.expected,
+                               self.found)
+            }
+        }
+
+        impl #impl_generics ::core::error::Error for #error_type #ty_generics #where_clause {}
+    })
+}
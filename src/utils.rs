@@ -1,10 +1,260 @@
-use quote::{Tokens, ToTokens};
-use syn::Ident;
+use proc_macro2::{Span, TokenStream};
+use syn::{Data, DeriveInput, Field, Fields, GenericParam, Generics, Ident, Index, LitStr, Member,
+          Path, Type, Variant, WherePredicate};
+use syn::punctuated::Punctuated;
+use syn::visit::{self, Visit};
+use crate::attrs::{Attrs, Options};
+use crate::diagnostics::{self, Error};
 
 pub fn numbered_vars(count: usize, prefix: &str) -> Vec<Ident> {
-    (0..count).map(|i| Ident::from(format!("__{}{}", prefix, i))).collect()
+    (0..count).map(|i| Ident::new(&format!("__{}{}", prefix, i), Span::call_site())).collect()
 }
 
-pub fn number_idents(count: usize) -> Vec<Ident> {
-    (0..count).map(|i| Ident::from(i.to_string())).collect()
+pub fn number_idents(count: usize) -> Vec<Index> {
+    (0..count).map(Index::from).collect()
+}
+
+/// Returns the member to access a field with, its name or otherwise its position like `0`
+pub fn field_member(index: usize, field: &Field) -> Member {
+    match field.ident {
+        Some(ref ident) => Member::Named(ident.clone()),
+        None => Member::Unnamed(Index::from(index)),
+    }
+}
+
+/// Adds a new type parameter without any bounds, for instance the type of the right hand side of
+/// a `Mul`-like operation.
+pub fn add_extra_generic_param(generics: &Generics, ident: &Ident) -> Generics {
+    let mut generics = generics.clone();
+    generics.params.push(GenericParam::Type(ident.clone().into()));
+    generics
+}
+
+/// Adds the predicates to the `where` clause of the generics
+pub fn add_extra_where_clauses<I>(generics: &Generics, predicates: I) -> Generics
+    where I: IntoIterator<Item = WherePredicate>
+{
+    let mut generics = generics.clone();
+    generics.make_where_clause().predicates.extend(predicates);
+    generics
+}
+
+/// Adds a `where` predicate like `Vec<T>: ::core::ops::Add<Output=Vec<T>>` for every field type
+/// that mentions one of the type parameters.
+/// Bounding the field types instead of the parameters themselves makes sure parameters that are
+/// only used as markers don't get bounds they can never satisfy.
+/// `PhantomData` fields and fields with the `skip` or `with` option don't use the trait, so their
+/// types don't need it.
+/// If the type has a `bound = "..."` option, those predicates are used instead.
+pub fn add_field_bounds<F>(input: &DeriveInput, attrs: &Attrs, bound: F) -> Result<Generics, Error>
+    where F: Fn(&Type) -> TokenStream
+{
+    let predicates = match bounds(&attrs.ty)? {
+        Some(predicates) => predicates,
+        None => {
+            let fields = all_fields(input)
+                .into_iter()
+                .filter(|f| !is_phantom_data(&f.ty))
+                .filter(|f| !attrs.field(f).flag("skip") && attrs.field(f).path("with").is_none());
+            field_types_using_params(input, fields)
+                .into_iter()
+                .map(|ty| {
+                    let bound = bound(ty);
+                    parse_quote!(#ty: #bound)
+                })
+                .collect()
+        }
+    };
+    Ok(add_extra_where_clauses(&input.generics, predicates))
+}
+
+/// Returns the `where` predicates given with `bound = "..."` options, if there are any.
+pub fn bounds(options: &Options) -> Result<Option<Vec<WherePredicate>>, Error> {
+    let predicates: Vec<_> = options.strs("bound").map(bound_value).collect();
+    if predicates.is_empty() {
+        return Ok(None);
+    }
+    diagnostics::collect(predicates).map(|p| Some(p.into_iter().flatten().collect()))
+}
+
+fn bound_value(bound: &LitStr) -> Result<Vec<WherePredicate>, Error> {
+    bound.parse_with(Punctuated::<WherePredicate, Token![,]>::parse_terminated)
+        .map(|predicates| predicates.into_iter().collect())
+        .map_err(|_| Error::new(bound.span(), format!("invalid bound `{}`", bound.value())))
+}
+
+/// Returns whether the type is `PhantomData`, which the operator derives rebuild instead of
+/// combining it.
+pub fn is_phantom_data(ty: &Type) -> bool {
+    match *ty {
+        Type::Path(ref ty) => {
+            ty.qself.is_none() &&
+            ty.path.segments.last().is_some_and(|segment| segment.ident == "PhantomData")
+        }
+        _ => false,
+    }
+}
+
+/// Returns the fields of a struct, the fields of every variant of an enum or the fields of a
+/// union.
+pub fn all_fields(input: &DeriveInput) -> Vec<&Field> {
+    match input.data {
+        Data::Struct(ref data) => data.fields.iter().collect(),
+        Data::Enum(ref data) => data.variants.iter().flat_map(|v| &v.fields).collect(),
+        Data::Union(ref data) => data.fields.named.iter().collect(),
+    }
+}
+
+/// Returns every distinct type of the fields that mentions one of the type parameters of the
+/// input, in the order they are first encountered.
+pub fn field_types_using_params<'a, I>(input: &DeriveInput, fields: I) -> Vec<&'a Type>
+    where I: IntoIterator<Item = &'a Field>
+{
+    types_using_params(input, fields.into_iter().map(|field| &field.ty))
+}
+
+/// Returns every distinct type that mentions one of the type parameters of the input, in the
+/// order they are first encountered.
+pub fn types_using_params<'a, I>(input: &DeriveInput, types: I) -> Vec<&'a Type>
+    where I: IntoIterator<Item = &'a Type>
+{
+    let params: Vec<_> = input.generics.type_params().map(|p| &p.ident).collect();
+    let mut tys: Vec<&Type> = vec![];
+    for ty in types {
+        if ty_uses_params(ty, &params) && !tys.contains(&ty) {
+            tys.push(ty);
+        }
+    }
+    tys
+}
+
+fn ty_uses_params(ty: &Type, params: &[&Ident]) -> bool {
+    let mut visitor = ParamVisitor {
+        params,
+        found: false,
+    };
+    visitor.visit_type(ty);
+    visitor.found
+}
+
+/// Looks for paths that start with one of the type parameters, like `T` or `T::Item`
+struct ParamVisitor<'a> {
+    params: &'a [&'a Ident],
+    found: bool,
+}
+
+impl<'a, 'ast> Visit<'ast> for ParamVisitor<'a> {
+    fn visit_path(&mut self, path: &'ast Path) {
+        if path.leading_colon.is_none() &&
+           self.params.iter().any(|param| path.segments[0].ident == **param) {
+            self.found = true;
+        }
+        visit::visit_path(self, path);
+    }
+}
+
+/// Returns the only field of a newtype, or otherwise the one that is marked with an attribute like
+/// `#[deref]`
+pub fn marked_field<'a>(input: &DeriveInput,
+                        attrs: &Attrs,
+                        fields: &'a Fields,
+                        trait_name: &str,
+                        attr_name: &str)
+                        -> Result<(Member, &'a Field), Error> {
+    let single = fields.len() == 1;
+    let mut marked = vec![];
+    for (i, field) in fields.iter().enumerate() {
+        let member = field_member(i, field);
+        if single || attrs.field(field).marked() {
+            marked.push((member, field));
+        }
+    }
+    match marked.len() {
+        1 => Ok(marked.pop().unwrap()),
+        0 => {
+            Err(Error::new_spanned(&input.ident,
+                                   format!("derive({}) needs a field to forward to, either the \
+                                            only field or one marked with #[{}]",
+                                           trait_name,
+                                           attr_name)))
+        }
+        _ => {
+            Err(Error::new_spanned(marked[1].1,
+                                   format!("only one field can be marked with #[{}]", attr_name)))
+        }
+    }
+}
+
+/// Applies a case policy like `snake_case` to the name of a variant, which is split into words at
+/// its uppercase letters
+pub fn rename(name: &str, rule: &str) -> String {
+    let mut words: Vec<String> = vec![];
+    let chars: Vec<char> = name.chars().collect();
+    for (i, &c) in chars.iter().enumerate() {
+        let boundary = i > 0 && c.is_uppercase() &&
+                       (!chars[i - 1].is_uppercase() ||
+                        chars.get(i + 1).is_some_and(|next| next.is_lowercase()));
+        if c == '_' {
+            words.push(String::new());
+        } else if boundary || words.is_empty() {
+            words.push(c.to_string());
+        } else {
+            words.last_mut().unwrap().push(c);
+        }
+    }
+    let words: Vec<String> = words.into_iter()
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_lowercase())
+        .collect();
+    let capitalize = |word: &String| {
+        let mut chars = word.chars();
+        match chars.next() {
+            Some(first) => first.to_uppercase().chain(chars).collect(),
+            None => String::new(),
+        }
+    };
+    match rule {
+        "lowercase" => words.concat(),
+        "UPPERCASE" => words.concat().to_uppercase(),
+        "PascalCase" => words.iter().map(capitalize).collect(),
+        "camelCase" => {
+            let pascal: String = words.iter().map(capitalize).collect();
+            let mut chars = pascal.chars();
+            match chars.next() {
+                Some(first) => first.to_lowercase().chain(chars).collect(),
+                None => pascal,
+            }
+        }
+        "snake_case" => words.join("_"),
+        "SCREAMING_SNAKE_CASE" => words.join("_").to_uppercase(),
+        "kebab-case" => words.join("-"),
+        _ => words.join("-").to_uppercase(),
+    }
+}
+
+/// Binds every field of a variant, like `Enum::Variant { 0: __0, 1: __1 }`, which works for both
+/// tuple and struct variants
+pub fn variant_pattern(input_type: &Ident, variant: &Variant, vars: &[Ident]) -> TokenStream {
+    let variant_ident = &variant.ident;
+    let members = variant.fields.iter().enumerate().map(|(i, f)| field_member(i, f));
+    quote!(#input_type::#variant_ident { #(#members: #vars),* })
+}
+
+/// A single field is returned as it is and multiple fields as a tuple
+pub fn payload(vars: &[Ident]) -> TokenStream {
+    match vars.len() {
+        1 => quote!(#(#vars)*),
+        _ => quote!((#(#vars),*)),
+    }
+}
+
+/// Returns the owned, borrowed and mutably borrowed types of a `payload`
+pub fn payload_types(types: &[&Type]) -> (TokenStream, TokenStream, TokenStream) {
+    match types.len() {
+        1 => {
+            let ty = types[0];
+            (quote!(#ty), quote!(&#ty), quote!(&mut #ty))
+        }
+        _ => (quote!((#(#types),*)), quote!((#(&#types),*)), quote!((#(&mut #types),*))),
+    }
 }
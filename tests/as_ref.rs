@@ -0,0 +1,65 @@
+#[macro_use]
+extern crate derive_more;
+
+#[derive(AsRef, AsMut)]
+struct MyInt(i32);
+
+#[derive(AsRef, AsMut)]
+struct Point {
+    #[as_ref]
+    name: String,
+    #[as_ref]
+    coords: Vec<i32>,
+    hidden: bool,
+}
+
+#[derive(AsRef, AsMut)]
+#[as_ref(forward)]
+struct Name(String);
+
+#[derive(AsRef)]
+struct Tagged<T> {
+    tag: u8,
+    #[as_ref(forward)]
+    value: Vec<T>,
+}
+
+fn len<T: AsRef<str>>(value: T) -> usize {
+    value.as_ref().len()
+}
+
+#[test]
+fn newtypes() {
+    let mut int = MyInt(1);
+    *int.as_mut() += 1;
+    assert_eq!(*int.as_ref(), 2);
+}
+
+#[test]
+fn marked_fields() {
+    let mut point = Point { name: "p".to_owned(), coords: vec![1, 2], hidden: true };
+    AsMut::<Vec<i32>>::as_mut(&mut point).push(3);
+    let coords: &Vec<i32> = point.as_ref();
+    assert_eq!(coords, &[1, 2, 3]);
+    let name: &String = point.as_ref();
+    assert_eq!(name, "p");
+    assert!(point.hidden);
+}
+
+#[test]
+fn forward() {
+    let mut name = Name("abc".to_owned());
+    assert_eq!(len(&name.0), 3);
+    let s: &str = name.as_ref();
+    assert_eq!(s, "abc");
+    let bytes: &[u8] = name.as_ref();
+    assert_eq!(bytes, b"abc");
+    let string: &mut str = name.as_mut();
+    string.make_ascii_uppercase();
+    assert_eq!(name.0, "ABC");
+
+    let tagged = Tagged { tag: 0, value: vec![1, 2] };
+    let slice: &[i32] = tagged.as_ref();
+    assert_eq!(slice, [1, 2]);
+    assert_eq!(tagged.tag, 0);
+}
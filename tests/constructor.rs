@@ -0,0 +1,43 @@
+#[macro_use]
+extern crate derive_more;
+
+use std::marker::PhantomData;
+
+#[derive(Debug, PartialEq, Constructor)]
+struct Unit;
+
+#[derive(Debug, PartialEq, Constructor)]
+struct Point(i32, i32);
+
+#[derive(Debug, PartialEq, Constructor)]
+struct User {
+    id: u32,
+    #[constructor(into)]
+    name: String,
+    #[constructor(skip)]
+    logins: Vec<u64>,
+}
+
+#[derive(Debug, PartialEq, Constructor)]
+#[constructor(into)]
+struct Labels(String, String);
+
+#[derive(Debug, PartialEq, Constructor)]
+struct Cache<K, V> {
+    key: K,
+    #[constructor(skip)]
+    value: Option<V>,
+    marker: PhantomData<V>,
+}
+
+#[test]
+fn constructors() {
+    assert_eq!(Unit::new(), Unit);
+    assert_eq!(Point::new(1, 2), Point(1, 2));
+    assert_eq!(User::new(1, "a"),
+               User { id: 1, name: "a".to_owned(), logins: vec![] });
+    assert_eq!(Labels::new("a", String::from("b")),
+               Labels("a".to_owned(), "b".to_owned()));
+    assert_eq!(Cache::<_, u8>::new("k"),
+               Cache { key: "k", value: None, marker: PhantomData });
+}
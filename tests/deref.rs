@@ -0,0 +1,60 @@
+#[macro_use]
+extern crate derive_more;
+
+#[derive(Deref, DerefMut)]
+struct MyInt(i32);
+
+#[derive(Deref, DerefMut)]
+struct Named {
+    inner: Vec<u8>,
+}
+
+#[derive(Deref, DerefMut)]
+struct Labeled<T> {
+    label: &'static str,
+    #[deref]
+    value: T,
+}
+
+#[derive(Deref)]
+#[deref(forward)]
+struct Name(Box<str>);
+
+#[derive(Deref, DerefMut)]
+struct Buffer {
+    len: usize,
+    #[deref(forward)]
+    data: Vec<u8>,
+}
+
+#[test]
+fn newtypes() {
+    let mut int = MyInt(5);
+    *int += 1;
+    assert_eq!(*int, 6);
+    let mut named = Named { inner: vec![] };
+    named.push(1);
+    assert_eq!(named.len(), 1);
+}
+
+#[test]
+fn marked_field() {
+    let mut labeled = Labeled { label: "x", value: 1.5 };
+    *labeled *= 2.0;
+    assert_eq!(*labeled, 3.0);
+    assert_eq!(labeled.label, "x");
+}
+
+#[test]
+fn forward() {
+    let name = Name("abc".into());
+    let s: &str = &name;
+    assert_eq!(s, "abc");
+    assert_eq!(name.len(), 3);
+
+    let mut buffer = Buffer { len: 0, data: vec![1, 2] };
+    buffer[0] = 5;
+    let slice: &[u8] = &buffer;
+    assert_eq!(slice, [5, 2]);
+    assert_eq!(buffer.len, 0);
+}
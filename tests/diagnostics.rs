@@ -0,0 +1,232 @@
+//! Compiles snippets that use the derives wrongly and checks the errors that are reported.
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Returns the proc-macro library that cargo built for the tests, next to the test executable
+fn derive_more_lib() -> PathBuf {
+    let deps = env::current_exe().unwrap().parent().unwrap().to_path_buf();
+    let prefix = format!("{}derive_more-", env::consts::DLL_PREFIX);
+    fs::read_dir(deps)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| {
+            let name = path.file_name().unwrap().to_string_lossy();
+            name.starts_with(&prefix) && name.ends_with(env::consts::DLL_SUFFIX)
+        })
+        .max_by_key(|path| fs::metadata(path).unwrap().modified().unwrap())
+        .expect("derive_more isn't built")
+}
+
+/// Compiles the source as a library and returns whether that succeeded, and what rustc reported
+fn compile(name: &str, source: &str) -> (bool, String) {
+    let dir = env::temp_dir().join(format!("derive_more_diagnostics_{}", name));
+    fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("lib.rs");
+    fs::write(&file, format!("#[macro_use]\nextern crate derive_more;\n{}", source)).unwrap();
+    let output = Command::new(env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string()))
+        .args(["--edition", "2018", "--crate-type", "lib", "--emit", "metadata"])
+        .arg("--out-dir")
+        .arg(&dir)
+        .arg("--extern")
+        .arg(format!("derive_more={}", derive_more_lib().display()))
+        .arg(&file)
+        .output()
+        .unwrap();
+    (output.status.success(), String::from_utf8(output.stderr).unwrap())
+}
+
+/// Compiles the source as a library and returns the errors rustc reported
+fn errors(name: &str, source: &str) -> String {
+    let (success, errors) = compile(name, source);
+    assert!(!success, "{} compiled without errors", name);
+    errors
+}
+
+/// Compiles the source as a library and returns the warnings rustc reported
+fn warnings(name: &str, source: &str) -> String {
+    let (success, warnings) = compile(name, source);
+    assert!(success, "{} didn't compile:\n{}", name, warnings);
+    warnings
+}
+
+fn assert_reported(errors: &str, messages: &[&str]) {
+    for message in messages {
+        assert!(errors.contains(&format!("error: {}", message)),
+                "`{}` was not reported in:\n{}",
+                message,
+                errors);
+    }
+}
+
+fn assert_warned(warnings: &str, messages: &[&str]) {
+    for message in messages {
+        assert!(warnings.contains(message),
+                "`{}` was not reported in:\n{}",
+                message,
+                warnings);
+    }
+}
+
+#[test]
+fn options_and_shape() {
+    let errors = errors("options_and_shape",
+                        r#"
+        #[derive(Mul)]
+        #[derive_more(bound = "T: Copy", unknown = "")]
+        enum MixedInts {
+            SmallInt(i32),
+            BigInt(i64),
+        }
+    "#);
+    assert_reported(&errors,
+                    &["unknown option `unknown` for derive(Mul)",
+                      "derive(Mul) cannot be used on enum `MixedInts`, only on structs"]);
+}
+
+#[test]
+fn options_and_union() {
+    let errors = errors("options_and_union",
+                        r#"
+        #[derive(Display)]
+        #[display(unknown)]
+        union Bits {
+            int: u32,
+            float: f32,
+        }
+    "#);
+    assert_reported(&errors,
+                    &["unknown option `unknown` for derive(Display)",
+                      "derive(Display) cannot be used on unions"]);
+}
+
+#[test]
+fn options_and_unit_struct() {
+    let errors = errors("options_and_unit_struct",
+                        r#"
+        #[derive(Into)]
+        #[into(unknown)]
+        struct Unit;
+    "#);
+    assert_reported(&errors,
+                    &["unknown option `unknown` for derive(Into)",
+                      "derive(Into) cannot be used on unit struct `Unit`"]);
+}
+
+#[test]
+fn options_and_struct() {
+    let errors = errors("options_and_struct",
+                        r#"
+        #[derive(IsVariant)]
+        #[is_variant(unknown)]
+        struct Single(i32);
+    "#);
+    assert_reported(&errors,
+                    &["unknown option `unknown` for derive(IsVariant)",
+                      "derive(IsVariant) can only be used on enums, not on struct `Single`"]);
+}
+
+#[test]
+fn unknown_derive_in_shared_options() {
+    let errors = errors("unknown_derive_in_shared_options",
+                        r#"
+        #[derive(Add)]
+        #[derive_more(Ad(bound = "T: Copy"))]
+        struct Wrapper<T>(T);
+    "#);
+    assert_reported(&errors, &["unknown derive `Ad` in #[derive_more(...)]"]);
+}
+
+#[test]
+fn misplaced_markers() {
+    let errors = errors("misplaced_markers",
+                        r#"
+        #[derive(From)]
+        enum Input {
+            Value(#[from] u8),
+        }
+    "#);
+    assert_reported(&errors, &["#[from] can only be used on a variant"]);
+}
+
+#[test]
+fn display_missing_positions() {
+    let errors = errors("display_missing_positions",
+                        r#"
+        #[derive(Display)]
+        #[display(fmt = "{} and {}")]
+        struct Single(i32);
+
+        #[derive(Display)]
+        #[display(fmt = "{:.*}")]
+        struct Star(f64, usize);
+    "#);
+    assert_reported(&errors,
+                    &["`Single` has no field 1",
+                      "a precision like `{:.*}` isn't supported, use one like `{:.1$}` or \
+                       `{:.precision$}` instead"]);
+}
+
+#[test]
+fn try_into_ambiguous_payloads() {
+    let warnings = warnings("try_into_ambiguous_payloads",
+                            r#"
+        #[derive(TryInto)]
+        pub enum Ambiguous {
+            Pair(i32, i32),
+            Tuple((i32, i32)),
+            One(u8),
+            Two(u8),
+            Three(u8),
+            #[try_into(ignore)]
+            Ignored(bool),
+            Kept(bool),
+        }
+    "#);
+    assert_warned(&warnings,
+                  &["no `TryFrom<Ambiguous>` is generated for `(i32, i32)`, because `Pair` and \
+                     `Tuple` both convert into it; mark all but one of them with \
+                     #[try_into(ignore)]",
+                    "no `TryFrom<Ambiguous>` is generated for `u8`, because `One`, `Two` and \
+                     `Three` all convert into it"]);
+    assert!(!warnings.contains("bool"), "{}", warnings);
+}
+
+#[test]
+fn from_ambiguous_payloads() {
+    let warnings = warnings("from_ambiguous_payloads",
+                            r#"
+        #[derive(From)]
+        pub enum Ambiguous {
+            Pair(i32, i32),
+            Tuple((i32, i32)),
+            First(u8),
+            Second(u8),
+            Third(u8),
+        }
+    "#);
+    assert_warned(&warnings,
+                  &["no `From<(i32, i32)>` is generated for `Ambiguous`, because `Pair` and \
+                     `Tuple` both convert from it; mark one of them with #[from], or the others \
+                     with #[from(ignore)]",
+                    "no `From<u8>` is generated for `Ambiguous`, because `First`, `Second` and \
+                     `Third` all convert from it"]);
+}
+
+#[test]
+fn from_listed_types() {
+    let warnings = warnings("from_listed_types",
+                            r#"
+        #[derive(From)]
+        pub enum Signal {
+            #[from(types(u8))]
+            Stop,
+            Value(u8),
+        }
+    "#);
+    assert_warned(&warnings,
+                  &["no `From<u8>` is generated for `Signal`, because `Stop` and `Value` both \
+                     convert from it"]);
+}
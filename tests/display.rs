@@ -0,0 +1,106 @@
+#![allow(dead_code)]
+#[macro_use]
+extern crate derive_more;
+
+#[derive(Display)]
+struct UserId(u64);
+
+#[derive(Display)]
+struct Name {
+    inner: String,
+}
+
+#[derive(Display)]
+#[display(fmt = "({x}, {y})")]
+struct Point2D {
+    x: i32,
+    y: i32,
+}
+
+#[derive(Display)]
+#[display(fmt = "{0}x{1} {0:?}")]
+struct Size(u32, u32);
+
+#[derive(Display)]
+struct Marker;
+
+#[derive(Display)]
+enum Error {
+    Io(String),
+    #[display(fmt = "invalid port {port:#x} on {host}")]
+    InvalidPort { host: String, port: u16 },
+    #[display(fmt = "line {0}, column {1}")]
+    Syntax(usize, usize, String),
+    #[display(fmt = "{{unexpected}}")]
+    Unexpected,
+    Unknown,
+}
+
+#[derive(Display)]
+#[display(fmt = "{value} ({unit:?})")]
+struct Tagged<T, U> {
+    value: T,
+    unit: U,
+}
+
+#[derive(Display)]
+struct Wrapper<T>(T);
+
+#[derive(Display)]
+#[display(fmt = "{} and {}, then {0} again")]
+struct Pair(&'static str, &'static str);
+
+#[derive(Display)]
+#[display(fmt = "[{value:>width$.precision$}] [{2:>1$}]")]
+struct Padded {
+    value: f64,
+    width: usize,
+    precision: usize,
+}
+
+#[derive(Display)]
+enum Column {
+    #[display(fmt = "{:0>1$}")]
+    Numbered(u32, usize),
+    #[display(fmt = "{name:width$}|")]
+    Named { name: &'static str, width: usize },
+}
+
+// An enum without variants can't be displayed, but deriving for it still compiles
+#[derive(Display)]
+enum Never {}
+
+#[test]
+fn display_structs() {
+    assert_eq!(UserId(42).to_string(), "42");
+    assert_eq!(format!("{:>4}", UserId(42)), "  42");
+    assert_eq!(Name { inner: "ferris".to_owned() }.to_string(), "ferris");
+    assert_eq!(Point2D { x: 1, y: -2 }.to_string(), "(1, -2)");
+    assert_eq!(Size(3, 4).to_string(), "3x4 3");
+    assert_eq!(Marker.to_string(), "Marker");
+    assert_eq!(Tagged { value: 1.5, unit: "m" }.to_string(), "1.5 (\"m\")");
+    assert_eq!(Wrapper("wrapped").to_string(), "wrapped");
+    assert_eq!(Pair("this", "that").to_string(), "this and that, then this again");
+}
+
+#[test]
+fn display_widths_and_precisions() {
+    let padded = Padded {
+        value: 1.2345,
+        width: 6,
+        precision: 2,
+    };
+    assert_eq!(padded.to_string(), "[  1.23] [     2]");
+    assert_eq!(Column::Numbered(7, 3).to_string(), "007");
+    assert_eq!(Column::Named { name: "id", width: 4 }.to_string(), "id  |");
+}
+
+#[test]
+fn display_enums() {
+    assert_eq!(Error::Io("disk full".to_owned()).to_string(), "disk full");
+    assert_eq!(Error::InvalidPort { host: "localhost".to_owned(), port: 255 }.to_string(),
+               "invalid port 0xff on localhost");
+    assert_eq!(Error::Syntax(1, 2, "".to_owned()).to_string(), "line 1, column 2");
+    assert_eq!(Error::Unexpected.to_string(), "{unexpected}");
+    assert_eq!(Error::Unknown.to_string(), "Unknown");
+}
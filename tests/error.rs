@@ -0,0 +1,102 @@
+#[macro_use]
+extern crate derive_more;
+
+use std::error::Error as _;
+use std::fmt;
+
+#[derive(Debug, Display, Error)]
+#[display(fmt = "invalid digit")]
+struct ParseError;
+
+#[derive(Debug, Display, Error)]
+#[display(fmt = "could not read {path}")]
+struct ReadError {
+    path: String,
+    source: std::io::Error,
+}
+
+#[derive(Debug, Display, Error, From)]
+enum ConfigError {
+    Parse(#[error(source)] ParseError),
+    Read(ReadError),
+    #[display(fmt = "missing key {key}")]
+    Missing { key: String },
+}
+
+#[derive(Debug, Error)]
+struct Wrapped<E> {
+    source: E,
+}
+
+impl<E: fmt::Display> fmt::Display for Wrapped<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "wrapped: {}", self.source)
+    }
+}
+
+#[derive(Debug, Display, Error)]
+#[display(fmt = "request failed")]
+struct Boxed {
+    source: Box<dyn std::error::Error + Send + Sync>,
+}
+
+#[derive(Debug, Display, Error)]
+enum Optional {
+    #[display(fmt = "maybe io")]
+    Io { source: Option<std::io::Error> },
+    #[display(fmt = "maybe boxed")]
+    Boxed(#[error(source)] Option<Box<dyn std::error::Error + Send + Sync>>),
+}
+
+#[derive(Debug, Display, Error)]
+#[display(fmt = "{source} failed")]
+struct Ignored {
+    #[error(ignore)]
+    source: String,
+}
+
+#[derive(Debug, Display, Error)]
+#[display(fmt = "boxed")]
+struct BoxedGeneric<E> {
+    source: Box<E>,
+}
+
+#[test]
+fn error_sources() {
+    assert!(ParseError.source().is_none());
+
+    let read = ReadError {
+        path: "config.toml".to_owned(),
+        source: std::io::Error::new(std::io::ErrorKind::NotFound, "not found"),
+    };
+    assert_eq!(read.source().unwrap().to_string(), "not found");
+
+    let parse: ConfigError = ParseError.into();
+    assert_eq!(parse.to_string(), "invalid digit");
+    assert_eq!(parse.source().unwrap().to_string(), "invalid digit");
+    // Read has a single field, but it isn't marked as the source
+    let read: ConfigError = read.into();
+    assert!(read.source().is_none());
+    assert!(ConfigError::Missing { key: "port".to_owned() }.source().is_none());
+
+    let wrapped = Wrapped { source: ParseError };
+    assert_eq!(wrapped.source().unwrap().to_string(), "invalid digit");
+}
+
+#[test]
+fn error_wrapped_sources() {
+    let boxed = Boxed { source: "timeout".into() };
+    assert_eq!(boxed.source().unwrap().to_string(), "timeout");
+
+    let io = std::io::Error::new(std::io::ErrorKind::NotFound, "not found");
+    assert_eq!(Optional::Io { source: Some(io) }.source().unwrap().to_string(), "not found");
+    assert!(Optional::Io { source: None }.source().is_none());
+    assert_eq!(Optional::Boxed(Some("timeout".into())).source().unwrap().to_string(),
+               "timeout");
+    assert!(Optional::Boxed(None).source().is_none());
+
+    assert!(Ignored { source: "fetching".to_owned() }.source().is_none());
+
+    let generic = BoxedGeneric { source: Box::new(ParseError) };
+    assert_eq!(generic.source().unwrap().to_string(), "invalid digit");
+}
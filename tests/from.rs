@@ -23,8 +23,82 @@ struct Point2D {
 enum MixedInts {
     SmallInt(i32),
     BigInt(i64),
+    #[from]
     TwoSmallInts(i32, i32),
     NamedSmallInts { x: i32, y: i32 },
+    #[from]
     UnsignedOne(u32),
     UnsignedTwo(u32),
 }
+
+#[derive(Debug, PartialEq, From)]
+enum Ignored {
+    Int(i32),
+    #[from(ignore)]
+    OtherInt(i32),
+    #[from(ignore)]
+    Unsigned(u32),
+}
+
+#[derive(Debug, PartialEq, From)]
+enum Shapes {
+    Circle { radius: u32 },
+    Rect { width: u8, height: u8 },
+    Line(i16, i16, i16, i16),
+    Empty,
+}
+
+#[test]
+fn variants_with_fields() {
+    assert_eq!(Shapes::from(2), Shapes::Circle { radius: 2 });
+    assert_eq!(Shapes::from((3, 4)), Shapes::Rect { width: 3, height: 4 });
+    assert_eq!(Shapes::from((0, 0, 1, 1)), Shapes::Line(0, 0, 1, 1));
+    assert!(matches!(MixedInts::from((1, 2)), MixedInts::TwoSmallInts(1, 2)));
+}
+
+#[test]
+fn chosen_variants() {
+    assert!(matches!(MixedInts::from(5u32), MixedInts::UnsignedOne(5)));
+    assert_eq!(Ignored::from(1), Ignored::Int(1));
+}
+
+#[derive(Debug, PartialEq)]
+struct Eof;
+
+#[derive(Debug, PartialEq)]
+struct Halt;
+
+#[derive(Debug, PartialEq, From)]
+enum Signal {
+    #[from]
+    Stop,
+    Value(u8),
+    Pause,
+}
+
+#[derive(Debug, PartialEq, From)]
+enum Token {
+    Word(&'static str),
+    #[from(types(Eof, Halt))]
+    Eof,
+    Empty,
+}
+
+#[derive(Debug, PartialEq, From)]
+enum Command {
+    #[from(types(u8, Halt))]
+    Stop,
+    #[from]
+    Value(u8),
+}
+
+#[test]
+fn unit_variants() {
+    assert_eq!(Signal::from(()), Signal::Stop);
+    assert_eq!(Signal::from(3), Signal::Value(3));
+    assert_eq!(Token::from(Eof), Token::Eof);
+    assert_eq!(Token::from(Halt), Token::Eof);
+    assert_eq!(Token::from("a"), Token::Word("a"));
+    assert_eq!(Command::from(3u8), Command::Value(3));
+    assert_eq!(Command::from(Halt), Command::Stop);
+}
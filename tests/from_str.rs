@@ -0,0 +1,79 @@
+#[macro_use]
+extern crate derive_more;
+
+use std::str::FromStr;
+
+#[derive(Debug, Eq, PartialEq, FromStr)]
+struct Port(u16);
+
+#[derive(Debug, Eq, PartialEq, FromStr)]
+struct Host {
+    name: String,
+}
+
+#[derive(Debug, Eq, PartialEq, FromStr)]
+struct Wrapper<T>(T);
+
+#[test]
+fn newtypes() {
+    assert_eq!("8080".parse(), Ok(Port(8080)));
+    assert_eq!(Port::from_str("70000"), Err(u16::from_str("70000").unwrap_err()));
+    assert_eq!("localhost".parse(), Ok(Host { name: "localhost".to_owned() }));
+    assert_eq!("1.5".parse(), Ok(Wrapper(1.5)));
+    assert!("x".parse::<Wrapper<i32>>().is_err());
+}
+
+#[derive(Debug, Eq, PartialEq, FromStr)]
+enum Color {
+    Red,
+    #[from_str(alias = "grey")]
+    Gray,
+}
+
+#[derive(Debug, Eq, PartialEq, FromStr)]
+#[from_str(rename_all = "snake_case")]
+enum LogLevel {
+    Debug,
+    WarnOnce,
+    #[from_str(alias = "err", alias = "fatal")]
+    HTTPError,
+}
+
+#[derive(Debug, Eq, PartialEq, FromStr)]
+#[from_str(rename_all = "kebab-case", case_insensitive)]
+enum Mode {
+    ReadOnly,
+    ReadWrite,
+}
+
+#[derive(Debug, Eq, PartialEq, FromStr)]
+#[derive_more(FromStr(rename_all = "SCREAMING_SNAKE_CASE"))]
+enum Shouting {
+    OneTwo,
+}
+
+#[test]
+fn enums() {
+    assert_eq!("Red".parse(), Ok(Color::Red));
+    assert_eq!("grey".parse(), Ok(Color::Gray));
+    assert_eq!("red".parse::<Color>(), Err(ParseColorError));
+    assert_eq!(ParseColorError.accepted(), &["Red", "Gray", "grey"]);
+    assert_eq!(ParseColorError.to_string(), "expected one of: `Red`, `Gray`, `grey`");
+
+    assert_eq!("warn_once".parse(), Ok(LogLevel::WarnOnce));
+    assert_eq!("http_error".parse(), Ok(LogLevel::HTTPError));
+    assert_eq!("fatal".parse(), Ok(LogLevel::HTTPError));
+    assert_eq!("Debug".parse::<LogLevel>(), Err(ParseLogLevelError));
+
+    assert_eq!("read-only".parse(), Ok(Mode::ReadOnly));
+    assert_eq!("READ-WRITE".parse(), Ok(Mode::ReadWrite));
+    assert_eq!(ParseModeError.accepted(), &["read-only", "read-write"]);
+
+    assert_eq!("ONE_TWO".parse(), Ok(Shouting::OneTwo));
+}
+
+#[test]
+fn enum_error() {
+    let error: Box<dyn std::error::Error> = Box::new(ParseModeError);
+    assert_eq!(error.to_string(), "expected one of: `read-only`, `read-write`");
+}
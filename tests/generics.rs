@@ -0,0 +1,130 @@
+#![allow(dead_code)]
+#[macro_use]
+extern crate derive_more;
+
+#[derive(From)]
+#[derive(Eq, PartialEq, Debug)]
+#[derive(Add, Sub, BitAnd, BitOr, BitXor)]
+#[derive(AddAssign, SubAssign)]
+#[derive(Mul, Div)]
+#[derive(Not, Neg)]
+struct Wrapped<T>(T);
+
+#[derive(From)]
+#[derive(Eq, PartialEq, Debug)]
+#[derive(Add, Sub)]
+#[derive(AddAssign)]
+#[derive(Mul)]
+#[derive(Neg)]
+struct Point2D<T: Clone> {
+    x: T,
+    y: T,
+}
+
+#[derive(From)]
+#[derive(Eq, PartialEq, Debug)]
+#[derive(Add)]
+#[derive(Mul)]
+struct Pair<A, B>(A, B) where A: Clone;
+
+#[derive(Eq, PartialEq, Debug)]
+#[derive(Add, Neg)]
+enum MixedNumbers<T, U> {
+    Small(T),
+    Big(U),
+    Both(T, U),
+    Named { t: T, u: U },
+}
+
+#[derive(From)]
+#[derive(Eq, PartialEq, Debug)]
+#[derive(Add, Not)]
+enum Maybe<T> {
+    Just(T),
+    Pair(T, T),
+}
+
+#[test]
+fn generic_structs() {
+    let a: Wrapped<i32> = 5.into();
+    assert_eq!(a + Wrapped(6), Wrapped(11));
+    assert_eq!(Wrapped(6) - Wrapped(5), Wrapped(1));
+    assert_eq!(Wrapped(6u8) & Wrapped(3), Wrapped(2));
+    assert_eq!(Wrapped(5) * 3, Wrapped(15));
+    assert_eq!(!Wrapped(true), Wrapped(false));
+    assert_eq!(-Wrapped(5), Wrapped(-5));
+    let mut b = Wrapped(1.5);
+    b += Wrapped(2.0);
+    b -= Wrapped(0.5);
+    assert_eq!(b, Wrapped(3.0));
+
+    let p: Point2D<i64> = (1, 2).into();
+    assert_eq!(p + Point2D { x: 3, y: 4 }, Point2D { x: 4, y: 6 });
+    assert_eq!(Point2D { x: 1, y: 2 } * 3, Point2D { x: 3, y: 6 });
+    assert_eq!(-Point2D { x: 1, y: 2 }, Point2D { x: -1, y: -2 });
+    let mut q = Point2D { x: 1u8, y: 2 };
+    q += Point2D { x: 1, y: 1 };
+    assert_eq!(q, Point2D { x: 2, y: 3 });
+
+    let pair: Pair<i32, i64> = (1, 2).into();
+    assert_eq!(pair + Pair(1, 1), Pair(2, 3));
+    assert_eq!(Pair(2, 3) * 2, Pair(4, 6));
+}
+
+#[test]
+fn generic_enums() {
+    assert_eq!(MixedNumbers::Small::<i32, i64>(1) + MixedNumbers::Small(2),
+               Ok(MixedNumbers::Small(3)));
+    assert_eq!(MixedNumbers::Both::<i32, i64>(1, 2) + MixedNumbers::Both(3, 4),
+               Ok(MixedNumbers::Both(4, 6)));
+    assert_eq!(-MixedNumbers::Named::<i32, i64> { t: 1, u: 2 },
+               MixedNumbers::Named { t: -1, u: -2 });
+
+    let m: Maybe<bool> = true.into();
+    assert_eq!(!m, Maybe::Just(false));
+    assert_eq!(Maybe::Pair(1, 2) + Maybe::Pair(3, 4), Ok(Maybe::Pair(4, 6)));
+    assert_eq!(Maybe::Pair(1, 2) + Maybe::Just(3), Err("Trying to add mismatched enum variants"));
+}
+
+/// Only adds the value, whatever the `Tag` is
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+struct Unit<Tag>(i32, ::std::marker::PhantomData<Tag>);
+
+impl<Tag> ::std::ops::Add for Unit<Tag> {
+    type Output = Unit<Tag>;
+    fn add(self, rhs: Unit<Tag>) -> Unit<Tag> {
+        Unit(self.0 + rhs.0, self.1)
+    }
+}
+
+#[derive(Eq, PartialEq, Debug)]
+struct Meters;
+
+#[derive(Eq, PartialEq, Debug)]
+#[derive(Add)]
+struct Tagged<T, Tag> {
+    value: T,
+    unit: Unit<Tag>,
+}
+
+#[derive(Eq, PartialEq, Debug)]
+#[derive(Add, Mul)]
+#[derive_more(bound = "T: Copy + ::std::ops::Add<Output = T>")]
+#[derive_more(Mul(bound = "__RhsT: ::std::ops::Mul<T, Output = T>"))]
+struct Overridden<T>(T);
+
+#[derive(Eq, PartialEq, Debug)]
+#[derive(Sub, Neg)]
+#[sub(bound = "T: ::std::ops::Sub<Output = T>")]
+#[derive_more(Neg(bound = "T: ::std::ops::Neg<Output = T>"))]
+struct Namespaced<T>(T);
+
+#[test]
+fn field_type_bounds() {
+    let meters = |value| Unit::<Meters>(value, ::std::marker::PhantomData);
+    assert_eq!(Tagged { value: 1, unit: meters(2) } + Tagged { value: 3, unit: meters(4) },
+               Tagged { value: 4, unit: meters(6) });
+    assert_eq!(Overridden(1) + Overridden(2), Overridden(3));
+    assert_eq!(Overridden(2) * 3, Overridden(6));
+    assert_eq!(-(Namespaced(5) - Namespaced(2)), Namespaced(-3));
+}
@@ -0,0 +1,39 @@
+#[macro_use]
+extern crate derive_more;
+
+use std::collections::HashMap;
+
+#[derive(Index, IndexMut)]
+struct Row(Vec<f64>);
+
+#[derive(Index, IndexMut)]
+struct Table<T> {
+    name: &'static str,
+    #[index]
+    rows: Vec<T>,
+}
+
+#[derive(Index)]
+struct Lookup {
+    map: HashMap<&'static str, i32>,
+}
+
+#[test]
+fn newtypes() {
+    let mut row = Row(vec![1.0, 2.0, 3.0]);
+    row[1] = 5.0;
+    assert_eq!(row[1], 5.0);
+    assert_eq!(row[1..], [5.0, 3.0]);
+
+    let mut map = HashMap::new();
+    map.insert("a", 1);
+    assert_eq!(Lookup { map }["a"], 1);
+}
+
+#[test]
+fn marked_field() {
+    let mut table = Table { name: "t", rows: vec!["a", "b"] };
+    table[0] = "c";
+    assert_eq!(table[..], ["c", "b"]);
+    assert_eq!(table.name, "t");
+}
@@ -0,0 +1,40 @@
+#[macro_use]
+extern crate derive_more;
+
+#[derive(Into)]
+struct MyInt(i32);
+
+#[derive(Into)]
+struct Point1D {
+    x: i32,
+}
+
+#[derive(Into)]
+struct MyInts(i32, i32);
+
+#[derive(Into)]
+struct Point2D {
+    x: i32,
+    y: i32,
+}
+
+#[derive(Into)]
+struct Wrapper<T>(Vec<T>);
+
+#[derive(Into)]
+struct Slice<'a>(&'a [u8]);
+
+#[test]
+fn newtypes() {
+    assert_eq!(5, MyInt(5).into());
+    assert_eq!(6, i32::from(Point1D { x: 6 }));
+    assert_eq!(vec![1, 2], Vec::from(Wrapper(vec![1, 2])));
+    let slice: &[u8] = Slice(b"abc").into();
+    assert_eq!(b"abc", slice);
+}
+
+#[test]
+fn multiple_fields() {
+    assert_eq!((1, 2), MyInts(1, 2).into());
+    assert_eq!((3, 4), <(i32, i32)>::from(Point2D { x: 3, y: 4 }));
+}
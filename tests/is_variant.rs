@@ -0,0 +1,25 @@
+#![allow(dead_code)]
+
+#[macro_use]
+extern crate derive_more;
+
+#[derive(IsVariant)]
+enum State<T> {
+    Idle,
+    SmallInt(u8),
+    Running { task: T },
+    HTTPError(u16, &'static str),
+}
+
+#[test]
+fn predicates() {
+    let idle: State<()> = State::Idle;
+    assert!(idle.is_idle());
+    assert!(!idle.is_small_int());
+
+    assert!(State::<()>::SmallInt(1).is_small_int());
+    let running = State::Running { task: "a" };
+    assert!(running.is_running());
+    assert!(!running.is_idle());
+    assert!(State::<()>::HTTPError(404, "").is_http_error());
+}
@@ -36,6 +36,7 @@ struct NormalStruct {
     int2: u64,
 }
 
+#[allow(dead_code)]
 #[derive(From)]
 #[derive(Eq, PartialEq, Debug)]
 struct NestedInt(MyInt);
@@ -45,7 +46,9 @@ struct NestedInt(MyInt);
 #[derive(Add, Sub)]
 enum SimpleMyIntEnum {
     Int(i32),
+    #[from(ignore)]
     _UnsignedOne(u32),
+    #[from(ignore)]
     _UnsignedTwo(u32),
 }
 #[derive(Eq, PartialEq, Debug)]
@@ -62,6 +65,7 @@ enum SimpleSignedIntEnum {
 #[derive(Neg)]
 enum SimpleEnum {
     Int(i32),
+    #[from(ignore)]
     _Ints(i32, i32),
     LabeledInts { a: i32, b: i32 },
     _SomeUnit,
@@ -74,16 +78,32 @@ enum MyIntEnum {
     SmallInt(i32),
     BigInt(i64),
     _TwoInts(i32, i32),
+    #[from(ignore)]
     _UnsignedOne(u32),
+    #[from(ignore)]
     _UnsignedTwo(u32),
     _Nothing,
 }
 
+// A variant with two fields and one with a tuple of the same types convert from the same type
+#[derive(Eq, PartialEq, Debug)]
+#[derive(From)]
+enum Coordinates {
+    #[from]
+    Pair(i32, i32),
+    _Tuple((i32, i32)),
+    #[from(ignore)]
+    _Bytes(u8, u8),
+    #[from(types((u8, u8)))]
+    Origin,
+}
+
 
 #[derive(Eq, PartialEq, Debug)]
 #[derive(Add, Mul)]
 struct DoubleUInt(u32, u32);
 
+#[allow(dead_code)]
 #[derive(Eq, PartialEq, Debug)]
 #[derive(Add, Mul)]
 struct DoubleUIntStruct {
@@ -102,6 +122,8 @@ fn main() {
     assert_eq!(-MyInt(5), (-5).into());
     assert_eq!(!MyBool(true), false.into());
     assert_eq!(MyIntEnum::SmallInt(5), 5.into());
+    assert_eq!(Coordinates::Pair(1, 2), (1, 2).into());
+    assert_eq!(Coordinates::Origin, (1u8, 2u8).into());
 
     assert_eq!(SimpleStruct { int1: 5 }, 5.into());
     assert_eq!(NormalStruct { int1: 5, int2: 6 }, (5, 6).into());
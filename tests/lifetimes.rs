@@ -0,0 +1,93 @@
+#![allow(dead_code)]
+#[macro_use]
+extern crate derive_more;
+
+use std::ops::{Add, AddAssign, Not};
+
+#[derive(From)]
+#[derive(Eq, PartialEq, Debug)]
+struct Slice<'a>(&'a [u8]);
+
+#[derive(From)]
+#[derive(Eq, PartialEq, Debug)]
+enum Token<'a> {
+    Word(&'a str),
+    Num(i64),
+    Pair(&'a str, &'a str),
+}
+
+#[derive(From)]
+#[derive(Eq, PartialEq, Debug)]
+struct Named<'a> {
+    name: &'a str,
+}
+
+#[derive(From)]
+#[derive(Eq, PartialEq, Debug)]
+struct Borrowed<'a, 'b: 'a, T: 'a> {
+    first: &'a T,
+    second: &'b str,
+}
+
+/// A borrowed label that stays the same under arithmetic
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+struct Label<'a>(&'a str);
+
+impl<'a> Add for Label<'a> {
+    type Output = Label<'a>;
+    fn add(self, _: Label<'a>) -> Label<'a> {
+        self
+    }
+}
+
+impl<'a> AddAssign for Label<'a> {
+    fn add_assign(&mut self, _: Label<'a>) {}
+}
+
+impl<'a> Not for Label<'a> {
+    type Output = Label<'a>;
+    fn not(self) -> Label<'a> {
+        self
+    }
+}
+
+#[derive(Eq, PartialEq, Debug)]
+#[derive(Add, AddAssign, Not)]
+struct Measurement<'a, T> {
+    value: T,
+    label: Label<'a>,
+}
+
+#[derive(Eq, PartialEq, Debug)]
+#[derive(Add, Not)]
+enum Reading<'a> {
+    Labeled(i32, Label<'a>),
+    Plain(i32),
+}
+
+#[test]
+fn from_with_lifetimes() {
+    let bytes = [1, 2, 3];
+    assert_eq!(Slice(&bytes), (&bytes[..]).into());
+    assert_eq!(Token::Word("word"), "word".into());
+    assert_eq!(Token::Num(5), 5.into());
+    assert_eq!(Named { name: "name" }, "name".into());
+    let x = 5;
+    assert_eq!(Borrowed { first: &x, second: "five" }, (&x, "five").into());
+}
+
+#[test]
+fn ops_with_lifetimes() {
+    let kg = Label("kg");
+    let sum = Measurement { value: 1, label: kg } + Measurement { value: 2, label: kg };
+    assert_eq!(sum, Measurement { value: 3, label: kg });
+    let mut total = Measurement { value: 1, label: kg };
+    total += Measurement { value: 2, label: kg };
+    assert_eq!(total, Measurement { value: 3, label: kg });
+    assert_eq!(!Measurement { value: true, label: kg },
+               Measurement { value: false, label: kg });
+
+    assert_eq!(Reading::Labeled(1, kg) + Reading::Labeled(2, kg),
+               Ok(Reading::Labeled(3, kg)));
+    assert_eq!(!Reading::Plain(0), Reading::Plain(!0));
+}
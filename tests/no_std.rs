@@ -0,0 +1,93 @@
+#![no_std]
+#![allow(dead_code)]
+#[macro_use]
+extern crate derive_more;
+
+#[derive(From, Add, Sub, BitAnd, BitOr, BitXor, Not, Neg)]
+#[derive(AddAssign, SubAssign, BitAndAssign, BitOrAssign, BitXorAssign)]
+#[derive(Mul, Div, Rem, Shr, Shl)]
+struct MyInts(i64, i64);
+
+#[derive(From, Add, Sub, BitAnd, BitOr, BitXor, Not, Neg)]
+#[derive(AddAssign, SubAssign, BitAndAssign, BitOrAssign, BitXorAssign)]
+#[derive(Mul, Div, Rem, Shr, Shl)]
+struct Point2D {
+    x: i64,
+    y: i64,
+}
+
+#[derive(From, Add, Sub, BitAnd, BitOr, BitXor, Not, Neg)]
+enum MixedInts {
+    SmallInt(i32),
+    BigInt(i64),
+    TwoSmallInts(i32, i32),
+    #[from]
+    NamedSmallInts { x: i32, y: i32 },
+    #[from]
+    Unit,
+}
+
+#[derive(From, Add, Neg, AddAssign, Mul)]
+struct Wrapped<T>(T);
+
+#[derive(From)]
+struct Slice<'a>(&'a [u8]);
+
+#[derive(FromStr, Into, Deref, DerefMut, Constructor)]
+struct Port(u16);
+
+#[derive(Index, IndexMut, AsRef, AsMut)]
+#[as_ref(forward)]
+struct Bytes([u8; 4]);
+
+#[derive(Debug, PartialEq, FromStr, IsVariant)]
+#[from_str(rename_all = "lowercase", case_insensitive)]
+enum Level {
+    Low,
+    High,
+}
+
+#[derive(Debug, Display, Error)]
+#[display(fmt = "invalid input")]
+struct InvalidInput;
+
+#[derive(Debug, Display, Error, From, Unwrap, TryUnwrap, TryInto)]
+enum Status {
+    Code(u16),
+    Invalid(#[error(source)] InvalidInput),
+    #[display(fmt = "{reason} ({code:#x})")]
+    Failed { code: u16, reason: &'static str },
+}
+
+#[test]
+fn no_std_derives() {
+    let ints = MyInts::from((1, 2)) + MyInts(3, 4);
+    assert_eq!((ints.0, ints.1), (4, 6));
+    let mut point = Point2D::from((1, 2)) * 3;
+    point -= Point2D { x: 1, y: 1 };
+    assert_eq!((point.x, point.y), (2, 5));
+    match MixedInts::from(5i32) + MixedInts::SmallInt(6) {
+        Ok(MixedInts::SmallInt(11)) => (),
+        _ => panic!("expected SmallInt(11)"),
+    }
+    assert!((MixedInts::Unit + MixedInts::Unit).is_err());
+    assert_eq!("8080".parse::<Port>().map(|port| port.0), Ok(8080));
+    assert_eq!("HIGH".parse(), Ok(Level::High));
+    assert!(Level::Low.is_low());
+    assert!(matches!(MixedInts::from(()), MixedInts::Unit));
+    assert_eq!(Status::Code(404).as_code(), Some(&404));
+    assert_eq!(Status::Code(500).try_unwrap_failed().map_err(|e| e.found).err(),
+               Some("Status::Code"));
+    assert_eq!(<u16 as core::convert::TryFrom<_>>::try_from(Status::Code(200)).ok(), Some(200));
+    assert_eq!(u16::from(Port::new(80)), 80);
+    let mut port = Port(80);
+    *port += 1;
+    assert_eq!(*port, 81);
+    let mut bytes = Bytes([1, 2, 3, 4]);
+    bytes[0] = 5;
+    assert_eq!(bytes[..2], [5, 2]);
+    let slice: &[u8] = bytes.as_ref();
+    assert_eq!(slice.len(), 4);
+    let wrapped = -Wrapped::from(2i32) * 4;
+    assert_eq!(wrapped.0, -8);
+}
@@ -0,0 +1,50 @@
+#[macro_use]
+extern crate derive_more;
+
+use std::marker::PhantomData;
+
+#[derive(Debug, PartialEq)]
+struct Meters;
+
+#[derive(Debug, PartialEq)]
+#[derive(Add, Sub, AddAssign, Mul, Neg)]
+struct Quantity<U>(f64, PhantomData<U>);
+
+#[derive(Debug, PartialEq)]
+#[derive(Add, SubAssign, Mul, Not)]
+struct Counter<U> {
+    count: u32,
+    unit: ::std::marker::PhantomData<U>,
+}
+
+#[derive(Debug, PartialEq)]
+#[derive(Add, Neg)]
+enum Measurement<U> {
+    Exact(i32, PhantomData<U>),
+    Range { low: i32, high: i32, unit: PhantomData<U> },
+}
+
+#[test]
+fn phantom_data_fields() {
+    let meters = |value| Quantity::<Meters>(value, PhantomData);
+    assert_eq!(meters(1.5) + meters(2.0), meters(3.5));
+    assert_eq!(meters(1.5) - meters(2.0), meters(-0.5));
+    assert_eq!(meters(1.5) * 2.0, meters(3.0));
+    assert_eq!(-meters(1.5), meters(-1.5));
+    let mut distance = meters(1.0);
+    distance += meters(2.0);
+    assert_eq!(distance, meters(3.0));
+
+    let counter = |count| Counter::<Meters> { count, unit: PhantomData };
+    assert_eq!(counter(1) + counter(2), counter(3));
+    assert_eq!(counter(2) * 3, counter(6));
+    assert_eq!(!counter(0), counter(!0));
+    let mut remaining = counter(5);
+    remaining -= counter(2);
+    assert_eq!(remaining, counter(3));
+
+    let exact = |value| Measurement::<Meters>::Exact(value, PhantomData);
+    assert_eq!(exact(1) + exact(2), Ok(exact(3)));
+    assert_eq!(-Measurement::<Meters>::Range { low: 1, high: 2, unit: PhantomData },
+               Measurement::Range { low: -1, high: -2, unit: PhantomData });
+}
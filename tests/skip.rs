@@ -0,0 +1,51 @@
+#[macro_use]
+extern crate derive_more;
+
+#[derive(Debug, Eq, PartialEq)]
+#[derive(Add, Sub, AddAssign, Mul, Neg)]
+struct Sample {
+    value: i32,
+    #[add(skip)]
+    #[sub(skip)]
+    #[add_assign(skip)]
+    #[mul(skip)]
+    #[neg(skip)]
+    id: u32,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+#[derive(Add, Mul)]
+struct Labeled<T, L>(T, #[add(skip)] #[mul(skip)] L);
+
+#[derive(Debug, Eq, PartialEq)]
+#[derive(Add, Not)]
+enum Reading {
+    Celsius(i32, #[add(skip)] #[not(skip)] bool),
+    Kelvin { value: u32, #[add(skip)] sensor: u8 },
+}
+
+#[test]
+fn skipped_fields_keep_lhs() {
+    let a = Sample { value: 3, id: 1 };
+    let b = Sample { value: 4, id: 2 };
+    assert_eq!(a + b, Sample { value: 7, id: 1 });
+    assert_eq!(Sample { value: 3, id: 1 } - Sample { value: 4, id: 2 },
+               Sample { value: -1, id: 1 });
+    let mut c = Sample { value: 1, id: 5 };
+    c += Sample { value: 2, id: 6 };
+    assert_eq!(c, Sample { value: 3, id: 5 });
+    assert_eq!(Sample { value: 3, id: 1 } * 2, Sample { value: 6, id: 1 });
+    assert_eq!(-Sample { value: 3, id: 1 }, Sample { value: -3, id: 1 });
+
+    assert_eq!(Labeled(1.5, "lhs") + Labeled(2.0, "rhs"), Labeled(3.5, "lhs"));
+    assert_eq!(Labeled(2, "lhs") * 3, Labeled(6, "lhs"));
+}
+
+#[test]
+fn skipped_variant_fields_keep_lhs() {
+    assert_eq!(Reading::Celsius(1, true) + Reading::Celsius(2, false),
+               Ok(Reading::Celsius(3, true)));
+    assert_eq!(Reading::Kelvin { value: 1, sensor: 7 } + Reading::Kelvin { value: 2, sensor: 8 },
+               Ok(Reading::Kelvin { value: 3, sensor: 7 }));
+    assert_eq!(!Reading::Celsius(1, true), Reading::Celsius(!1, true));
+}
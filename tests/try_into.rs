@@ -0,0 +1,66 @@
+#![allow(dead_code)]
+
+#[macro_use]
+extern crate derive_more;
+
+use std::convert::{TryFrom, TryInto};
+
+#[derive(Debug, PartialEq, TryInto)]
+enum MixedInts {
+    SmallInt(i32),
+    BigInt(i64),
+    TwoSmallInts(i32, i32),
+    #[try_into(ignore)]
+    NamedSmallInts { x: i32, y: i32 },
+    UnsignedOne(u32),
+    #[try_into(ignore)]
+    UnsignedTwo(u32),
+    Mixed { small: u8, flag: bool },
+    Paired((u8, u8)),
+    #[try_into(ignore)]
+    Split(u8, u8),
+    Nothing,
+}
+
+#[derive(Debug, PartialEq, TryInto)]
+enum Single {
+    Value(String),
+}
+
+#[derive(Debug, PartialEq, TryInto)]
+enum Borrowed<'a, T> {
+    Slice(&'a [T]),
+    Length(usize),
+}
+
+#[test]
+fn single_fields() {
+    assert_eq!(i32::try_from(MixedInts::SmallInt(1)), Ok(1));
+    assert_eq!(i64::try_from(MixedInts::BigInt(2)), Ok(2));
+    assert_eq!(i32::try_from(MixedInts::BigInt(2)), Err(MixedInts::BigInt(2)));
+    let big: Result<i64, _> = MixedInts::Nothing.try_into();
+    assert_eq!(big, Err(MixedInts::Nothing));
+    assert_eq!(String::try_from(Single::Value("a".to_owned())), Ok("a".to_owned()));
+    assert_eq!(<&[u8]>::try_from(Borrowed::Slice(b"ab")), Ok(&b"ab"[..]));
+    assert_eq!(usize::try_from(Borrowed::<u8>::Length(3)), Ok(3));
+}
+
+#[test]
+fn multiple_fields() {
+    let mixed = MixedInts::Mixed { small: 1, flag: true };
+    assert_eq!(<(u8, bool)>::try_from(mixed), Ok((1, true)));
+    let small = MixedInts::SmallInt(1);
+    assert_eq!(<(u8, bool)>::try_from(small), Err(MixedInts::SmallInt(1)));
+}
+
+#[test]
+fn ignored_variants() {
+    assert_eq!(u32::try_from(MixedInts::UnsignedOne(1)), Ok(1));
+    assert_eq!(u32::try_from(MixedInts::UnsignedTwo(2)), Err(MixedInts::UnsignedTwo(2)));
+    assert_eq!(<(i32, i32)>::try_from(MixedInts::TwoSmallInts(1, 2)), Ok((1, 2)));
+    let named = MixedInts::NamedSmallInts { x: 1, y: 2 };
+    assert_eq!(<(i32, i32)>::try_from(named),
+               Err(MixedInts::NamedSmallInts { x: 1, y: 2 }));
+    assert_eq!(<(u8, u8)>::try_from(MixedInts::Paired((1, 2))), Ok((1, 2)));
+    assert_eq!(<(u8, u8)>::try_from(MixedInts::Split(1, 2)), Err(MixedInts::Split(1, 2)));
+}
@@ -0,0 +1,60 @@
+#[macro_use]
+extern crate derive_more;
+
+#[derive(Debug, PartialEq, Unwrap, TryUnwrap)]
+enum MixedInts {
+    SmallInt(i32),
+    BigInt(i64),
+    TwoSmallInts(i32, i32),
+    NamedSmallInts { x: i32, y: i32 },
+    Nothing,
+}
+
+#[derive(Debug, PartialEq, Unwrap, TryUnwrap)]
+enum Maybe<'a, T> {
+    Just(&'a T),
+    Empty,
+}
+
+#[test]
+fn unwrap() {
+    assert_eq!(MixedInts::SmallInt(1).unwrap_small_int(), 1);
+    assert_eq!(MixedInts::TwoSmallInts(1, 2).unwrap_two_small_ints(), (1, 2));
+    assert_eq!(MixedInts::NamedSmallInts { x: 3, y: 4 }.unwrap_named_small_ints(), (3, 4));
+    MixedInts::Nothing.unwrap_nothing();
+    assert_eq!(Maybe::Just(&5).unwrap_just(), &5);
+}
+
+#[test]
+#[should_panic(expected = "called `MixedInts::unwrap_small_int()` on a `MixedInts::BigInt` value")]
+fn unwrap_panics() {
+    MixedInts::BigInt(1).unwrap_small_int();
+}
+
+#[test]
+fn as_ref() {
+    let mut value = MixedInts::TwoSmallInts(1, 2);
+    assert_eq!(value.as_two_small_ints(), Some((&1, &2)));
+    assert_eq!(value.as_small_int(), None);
+    if let Some((x, _)) = value.as_two_small_ints_mut() {
+        *x = 5;
+    }
+    assert_eq!(value, MixedInts::TwoSmallInts(5, 2));
+    assert_eq!(MixedInts::SmallInt(1).as_small_int_mut(), Some(&mut 1));
+}
+
+#[test]
+fn try_unwrap() {
+    assert_eq!(MixedInts::BigInt(2).try_unwrap_big_int().unwrap(), 2);
+    let error = MixedInts::SmallInt(1).try_unwrap_big_int().unwrap_err();
+    assert_eq!(error.expected, "MixedInts::BigInt");
+    assert_eq!(error.found, "MixedInts::SmallInt");
+    assert_eq!(error.to_string(),
+               "expected a `MixedInts::BigInt` value, found a `MixedInts::SmallInt` value");
+    assert_eq!(error.input, MixedInts::SmallInt(1));
+
+    let error = Maybe::<u8>::Empty.try_unwrap_just().unwrap_err();
+    assert_eq!(error.input, Maybe::Empty);
+    assert_eq!(format!("{:?}", error),
+               r#"TryUnwrapMaybeError { expected: "Maybe::Just", found: "Maybe::Empty", .. }"#);
+}
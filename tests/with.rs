@@ -0,0 +1,55 @@
+#[macro_use]
+extern crate derive_more;
+
+#[derive(Debug, Eq, PartialEq)]
+#[derive(Add, AddAssign)]
+struct Sample {
+    value: u32,
+    #[add(with = "std::cmp::max")]
+    #[add_assign(with = "max_assign")]
+    timestamp: u64,
+    #[add(with = "concat")]
+    #[add_assign(with = "Vec::extend")]
+    tags: Vec<&'static str>,
+}
+
+fn max_assign(lhs: &mut u64, rhs: u64) {
+    *lhs = std::cmp::max(*lhs, rhs);
+}
+
+fn concat<T>(mut lhs: Vec<T>, rhs: Vec<T>) -> Vec<T> {
+    lhs.extend(rhs);
+    lhs
+}
+
+#[derive(Debug, Eq, PartialEq)]
+#[derive(Mul)]
+#[mul(bound = "__RhsT: Copy + Into<u64> + std::ops::Mul<u32, Output = u32>")]
+struct Scaled(u32, #[mul(with = "repeat")] String);
+
+fn repeat<R: Into<u64>>(lhs: String, rhs: R) -> String {
+    lhs.repeat(rhs.into() as usize)
+}
+
+#[derive(Debug, Eq, PartialEq)]
+#[derive(Sub)]
+enum Span {
+    Seconds(#[sub(with = "u64::saturating_sub")] u64),
+    Named { start: i64, #[sub(with = "std::cmp::min")] end: i64 },
+}
+
+#[test]
+fn with_functions() {
+    let sample = |value, timestamp, tags| Sample { value, timestamp, tags };
+    assert_eq!(sample(1, 10, vec!["a"]) + sample(2, 5, vec!["b"]),
+               sample(3, 10, vec!["a", "b"]));
+    let mut total = sample(1, 5, vec!["a"]);
+    total += sample(2, 10, vec!["b"]);
+    assert_eq!(total, sample(3, 10, vec!["a", "b"]));
+
+    assert_eq!(Scaled(2, "ab".to_owned()) * 3, Scaled(6, "ababab".to_owned()));
+
+    assert_eq!(Span::Seconds(1) - Span::Seconds(2), Ok(Span::Seconds(0)));
+    assert_eq!(Span::Named { start: 5, end: 9 } - Span::Named { start: 1, end: 3 },
+               Ok(Span::Named { start: 4, end: 3 }));
+}